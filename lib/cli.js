@@ -2,17 +2,57 @@
 const addon = require('../native');
 // fs without promises is kinda cursed ngl
 const fs = require('fs/promises')
+const path = require('path')
 
-if(process.argv.length > 2) {
-  for(let fileName of process.argv.slice(2)) {
+const args = process.argv.slice(2)
+const flags = args.filter(arg => arg.startsWith('--'))
+const fileNames = args.filter(arg => !arg.startsWith('--'))
+
+const sourceMap = flags.includes('--source-map')
+const extractComments = flags.includes('--extract-comments')
+const htmlInline = flags.includes('--html-inline')
+const comments = flags
+  .filter(flag => flag.startsWith('--comments='))
+  .map(flag => flag.slice('--comments='.length))
+  .pop()
+
+if(fileNames.length > 0) {
+  for(let fileName of fileNames) {
     fs.readFile(fileName)
-    .then(file => {
-      const output = addon.minify(file.toString())
+    .then(async file => {
+      // `foo.js` gets `foo.min.js.map` and `foo.min.js.LICENSE.txt` next to it
+      const outName = fileName.replace(/(\.js)?$/, '.min.js')
+      const options = { comments, extract_comments: extractComments, html_inline: htmlInline }
+      if(sourceMap) {
+        Object.assign(options, {
+          source_map: true,
+          source_file: path.basename(fileName),
+          file: path.basename(outName),
+          source_map_url: path.basename(outName) + '.map',
+        })
+      } else if(extractComments) {
+        options.file = path.basename(outName)
+      }
+      const result = addon.minify(file.toString(), options)
+      let output = result
+      if(typeof result !== 'string') {
+        output = result.code
+        if(result.map) {
+          await fs.writeFile(outName + '.map', result.map)
+        }
+        if(result.license) {
+          await fs.writeFile(outName + '.LICENSE.txt', result.license)
+        }
+      }
       console.log(`FILE: ${fileName}\n`)
       console.log(output)
     })
   }
 } else {
   console.log(`Welcome to crisp!
-  Pass a file as an argument and I'll handle it for you`)
-}
\ No newline at end of file
+  Pass a file as an argument and I'll handle it for you
+  Add --source-map to write a source map next to each file
+  Add --comments=license (or none, all, /regex/) to keep comments
+  Add --extract-comments to move license comments to a .LICENSE.txt file
+  Add --html-inline to make the output safe to put inside a <script> tag`)
+}
@@ -0,0 +1,224 @@
+use std::ops::{Deref, Range};
+
+use super::token::{Comment, Token};
+
+/// A place in the input, counted from zero. Columns are in UTF-16 code units,
+/// like in source maps.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// The token stream that the transforms work on. Each token keeps the
+/// position it was read from, or `None` for tokens with no place in the
+/// input like inserted semicolons, and the comments that came before it.
+/// Renamed identifiers also keep the name they were read with.
+#[derive(Default)]
+pub struct TokenBuffer {
+    tokens: Vec<Token>,
+    starts: Vec<Option<Position>>,
+    comments: Vec<Vec<Comment>>,
+    names: Vec<Option<String>>,
+    /// The comments after the last token.
+    trailing: Vec<Comment>,
+}
+
+impl TokenBuffer {
+    pub fn new() -> TokenBuffer {
+        TokenBuffer::default()
+    }
+
+    pub fn push(&mut self, tok: Token, start: Option<Position>, comments: Vec<Comment>) {
+        self.tokens.push(tok);
+        self.starts.push(start);
+        self.comments.push(comments);
+        self.names.push(None);
+    }
+
+    pub fn start(&self, i: usize) -> Option<Position> {
+        self.starts[i]
+    }
+
+    pub fn comments(&self, i: usize) -> &[Comment] {
+        &self.comments[i]
+    }
+
+    pub fn set_trailing(&mut self, comments: Vec<Comment>) {
+        self.trailing = comments;
+    }
+
+    pub fn trailing(&self) -> &[Comment] {
+        &self.trailing
+    }
+
+    /// The name the identifier at `i` had in the input, if it was renamed.
+    pub fn original_name(&self, i: usize) -> Option<&str> {
+        self.names[i].as_deref()
+    }
+
+    /// Renames the identifier at `i`, keeping its position and comments.
+    pub fn rename(&mut self, i: usize, name: String) {
+        if let Token::Ident(old) = std::mem::replace(&mut self.tokens[i], Token::Ident(name)) {
+            if self.names[i].is_none() {
+                self.names[i] = Some(old);
+            }
+        }
+    }
+
+    /// Replaces the tokens in `range` with `replacement`. Replacement tokens
+    /// that were copied out of `range` keep their position, and new ones take
+    /// the position of the first token they replace. Comments stay with the
+    /// tokens that were copied, and the ones that can't be dropped move to
+    /// the first token after them.
+    pub fn splice<I: IntoIterator<Item = Token>>(&mut self, range: Range<usize>, replacement: I) {
+        let fallback = range
+            .clone()
+            .find_map(|i| self.starts[i])
+            .or_else(|| self.starts.get(range.end).copied().flatten());
+        let mut next = range.start;
+        let mut starts = Vec::new();
+        let mut comments = Vec::new();
+        let mut names = Vec::new();
+        let replacement: Vec<Token> = replacement.into_iter().collect();
+        for tok in &replacement {
+            match (next..range.end).find(|&i| self.tokens[i] == *tok) {
+                Some(i) => {
+                    starts.push(self.starts[i]);
+                    comments.push(std::mem::take(&mut self.comments[i]));
+                    names.push(self.names[i].take());
+                    next = i + 1;
+                }
+                None => {
+                    starts.push(fallback);
+                    comments.push(Vec::new());
+                    names.push(None);
+                }
+            }
+        }
+        let carried = self.carried_comments(range.clone(), !replacement.is_empty());
+        match comments.first_mut() {
+            Some(first) => {
+                first.splice(0..0, carried);
+            }
+            None => self.carry(range.end, carried),
+        }
+        self.tokens.splice(range.clone(), replacement);
+        self.starts.splice(range.clone(), starts);
+        self.comments.splice(range.clone(), comments);
+        self.names.splice(range, names);
+    }
+
+    /// Removes the tokens in `range`, moving the comments that can't be
+    /// dropped to the token after them.
+    pub fn drain(&mut self, range: Range<usize>) {
+        let carried = self.carried_comments(range.clone(), false);
+        self.carry(range.end, carried);
+        self.tokens.drain(range.clone());
+        self.starts.drain(range.clone());
+        self.comments.drain(range.clone());
+        self.names.drain(range);
+    }
+
+    /// Takes the comments of the tokens in `range` that have to outlive
+    /// them: license comments, and annotations on a `(` that is unwrapped
+    /// from the call it annotates when `contents_stay`.
+    fn carried_comments(&mut self, range: Range<usize>, contents_stay: bool) -> Vec<Comment> {
+        let mut carried = Vec::new();
+        for i in range {
+            let unwrapped = contents_stay && self.tokens[i] == Token::LeftParen;
+            for comment in std::mem::take(&mut self.comments[i]) {
+                if comment.is_license() || (unwrapped && comment.annotation().is_some()) {
+                    carried.push(comment);
+                }
+            }
+        }
+        carried
+    }
+
+    /// Puts `comments` in front of the ones of the token at `i`, or after the
+    /// last token.
+    fn carry(&mut self, i: usize, comments: Vec<Comment>) {
+        match self.comments.get_mut(i) {
+            Some(next) => {
+                next.splice(0..0, comments);
+            }
+            None => self.trailing.extend(comments),
+        }
+    }
+}
+
+impl Deref for TokenBuffer {
+    type Target = [Token];
+
+    fn deref(&self) -> &[Token] {
+        &self.tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::minify::{minify_with, Minifier};
+    use crate::options::{Comments, MinifyOptions};
+
+    #[test]
+    fn keeps_license_comments_of_removed_statements() {
+        let drop_debugger = |input| {
+            minify_with(input, |o| {
+                o.comments = Comments::License;
+                o.drop_debugger = true;
+            })
+        };
+        assert_eq!(
+            drop_debugger("/*! license */ debugger; a()"),
+            "/*! license */a()"
+        );
+        assert_eq!(
+            drop_debugger("a(); /*! license */ debugger"),
+            "a()/*! license */"
+        );
+        let dead_code = |input| {
+            minify_with(input, |o| {
+                o.comments = Comments::License;
+                o.dead_code = true;
+            })
+        };
+        assert_eq!(
+            dead_code("/*! license */ if (false) { a() } b()"),
+            "/*! license */b()"
+        );
+        let drop_pure_funcs = |input| {
+            minify_with(input, |o| {
+                o.comments = Comments::License;
+                o.pure_funcs = vec!["console.log".to_string()];
+            })
+        };
+        assert_eq!(
+            drop_pure_funcs("/*! license */ console.log(x); y()"),
+            "/*! license */y()"
+        );
+    }
+
+    #[test]
+    fn extracts_license_comments_of_removed_statements() {
+        let options = MinifyOptions {
+            comments: Comments::License,
+            drop_debugger: true,
+            extract_comments: true,
+            ..MinifyOptions::default()
+        };
+        let output = Minifier::with_options(Lexer::new("/*! license */ debugger; a()"), options)
+            .generate()
+            .unwrap();
+        assert_eq!(output.license.as_deref(), Some("/*! license */\n"));
+    }
+
+    #[test]
+    fn keeps_annotations_of_unwrapped_calls() {
+        let drop_parens = |input| minify_with(input, |o| o.drop_parens = true);
+        assert_eq!(drop_parens("x = /*#__PURE__*/(f())"), "x=/*#__PURE__*/f()");
+        let dead_code = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(dead_code("if (false) { /*#__PURE__*/a() } b()"), "b()");
+    }
+}
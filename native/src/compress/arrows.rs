@@ -0,0 +1,111 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, Token};
+use crate::util;
+
+/// Shortens arrow functions, like `(a)=>{return x}` into `a=>x`.
+pub fn compact_arrows(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::LeftParen if is_single_param(tokens, i) => {
+                let param = tokens[i + 1].clone();
+                tokens.splice(i..i + 3, vec![param]);
+            }
+            Token::LeftBracket if i > 0 && tokens[i - 1] == Token::Operator(Operator::Arrow) => {
+                if let Some((close, body)) = concise_body(tokens, i) {
+                    // the body is looked at again, for arrows nested in it
+                    tokens.splice(i..close + 1, body);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Whether the `(` at `open` holds the only parameter of an arrow function,
+/// and it is a plain name, so the parentheses can go.
+pub fn is_single_param(tokens: &[Token], open: usize) -> bool {
+    matches!(tokens.get(open + 1), Some(Token::Ident(_)))
+        && tokens.get(open + 2) == Some(&Token::RightParen)
+        && tokens.get(open + 3) == Some(&Token::Operator(Operator::Arrow))
+}
+
+/// For an arrow function body `{return x}` starting at `open`, its `}` and
+/// the expression that can replace it. `{return}` becomes `{}`.
+fn concise_body(tokens: &[Token], open: usize) -> Option<(usize, Vec<Token>)> {
+    let close = util::matching_close(tokens, open)?;
+    if tokens.get(open + 1) != Some(&Token::Keyword(Keyword::RETURN))
+        || util::statement_end(tokens, open + 1) != close
+    {
+        return None;
+    }
+    let mut end = close;
+    if tokens[end - 1] == Token::Semicolon {
+        end -= 1;
+    }
+    let value = &tokens[open + 2..end];
+    if value.is_empty() {
+        return Some((close, vec![Token::LeftBracket, Token::RightBracket]));
+    }
+    if util::operand_end(tokens, open + 2, precedence::COMMA) != end {
+        return None;
+    }
+    // a block body ends the arrow function, an expression could take in
+    // what comes after it
+    let ends_function = matches!(
+        tokens.get(close + 1),
+        None | Some(Token::Comma)
+            | Some(Token::Semicolon)
+            | Some(Token::RightParen)
+            | Some(Token::RightBrace)
+            | Some(Token::RightBracket)
+            | Some(Token::Operator(Operator::Colon))
+    );
+    if !ends_function {
+        return None;
+    }
+    let mut body = value.to_vec();
+    // `=>{}` would be a block, and `=>a,b` would end the function at the
+    // comma
+    if value[0] == Token::LeftBracket || util::expression_precedence(value) < precedence::ASSIGNMENT
+    {
+        body.insert(0, Token::LeftParen);
+        body.push(Token::RightParen);
+    }
+    Some((close, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn shortens_arrows() {
+        let compact_arrows = |input| minify_with(input, |o| o.arrows = true);
+        assert_eq!(compact_arrows("x = (a) => { return a }"), "x=a=>a");
+        assert_eq!(
+            compact_arrows("x = (a, b) => { return a + b }"),
+            "x=(a,b)=>a+b"
+        );
+        assert_eq!(compact_arrows("x = async (a) => a"), "x=async a=>a");
+    }
+
+    #[test]
+    fn wraps_bodies_that_need_it() {
+        let compact_arrows = |input| minify_with(input, |o| o.arrows = true);
+        assert_eq!(
+            compact_arrows("x = a => { return { b: 1 } }"),
+            "x=a=>({b:1})"
+        );
+        assert_eq!(compact_arrows("x = (a) => { return a, b }"), "x=a=>(a,b)");
+    }
+
+    #[test]
+    fn keeps_parens_around_patterns() {
+        let compact_arrows = |input| minify_with(input, |o| o.arrows = true);
+        assert_eq!(compact_arrows("x = (a = 1) => a"), "x=(a=1)=>a");
+        assert_eq!(compact_arrows("x = ([a]) => a"), "x=([a])=>a");
+    }
+}
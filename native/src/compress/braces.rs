@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, Token};
+use crate::util;
+
+/// Removes the braces around a block holding a single statement that is the
+/// body of an `if`, `else`, loop or `with`, like `if(a){b()}` into `if(a)b()`.
+pub fn drop_braces(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != Token::LeftBracket || !util::is_body_position(tokens, i) {
+            i += 1;
+            continue;
+        }
+        let close = match util::matching_close(tokens, i) {
+            Some(close) => close,
+            None => break,
+        };
+        let body = i + 1..close;
+        if body.is_empty()
+            || util::statement_end(tokens, body.start) != body.end
+            || util::has_lexical_declaration(&tokens[body.clone()])
+        {
+            i += 1;
+            continue;
+        }
+        let next = tokens.get(close + 1);
+        // `if(a){if(b)c()}else d()` would give the `else` to the inner `if`
+        if next == Some(&Token::Keyword(Keyword::ELSE))
+            && util::has_top_level(&tokens[body.clone()], |tok| {
+                *tok == Token::Keyword(Keyword::IF)
+            })
+        {
+            i += 1;
+            continue;
+        }
+        let mut replacement = tokens[body.clone()].to_vec();
+        let ends_block = matches!(
+            next,
+            None | Some(Token::Semicolon) | Some(Token::RightBracket)
+        );
+        if !ends_block && needs_semicolon(tokens, body) {
+            replacement.push(Token::Semicolon);
+        }
+        tokens.splice(i..close + 1, replacement);
+        i += 1;
+    }
+}
+
+/// Whether the statement in `range` has to be followed by a semicolon when
+/// another statement or an `else` comes after it, rather than ending with a
+/// block.
+fn needs_semicolon(tokens: &[Token], range: Range<usize>) -> bool {
+    if range.is_empty() {
+        return true;
+    }
+    if tokens[range.end - 1] == Token::Semicolon {
+        return false;
+    }
+    let start = range.start;
+    match &tokens[start] {
+        Token::LeftBracket | Token::Keyword(Keyword::TRY) | Token::Keyword(Keyword::SWITCH) => {
+            false
+        }
+        Token::Keyword(Keyword::IF) => match header_end(tokens, start) {
+            Some(close) => {
+                let then_end = util::statement_end(tokens, close + 1);
+                let body = if tokens.get(then_end) == Some(&Token::Keyword(Keyword::ELSE)) {
+                    then_end + 1
+                } else {
+                    close + 1
+                };
+                needs_semicolon(tokens, body..range.end)
+            }
+            None => true,
+        },
+        Token::Keyword(Keyword::FOR)
+        | Token::Keyword(Keyword::WHILE)
+        | Token::Keyword(Keyword::WITH) => match header_end(tokens, start) {
+            Some(close) => needs_semicolon(tokens, close + 1..range.end),
+            None => true,
+        },
+        Token::Ident(_) if tokens.get(start + 1) == Some(&Token::Operator(Operator::Colon)) => {
+            needs_semicolon(tokens, start + 2..range.end)
+        }
+        _ => true,
+    }
+}
+
+/// The `)` that closes the head of the statement starting at `start`.
+fn header_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let open = (start..tokens.len()).find(|&i| tokens[i] == Token::LeftParen)?;
+    util::matching_close(tokens, open)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_braces_around_one_statement() {
+        let drop_braces = |input| minify_with(input, |o| o.drop_braces = true);
+        assert_eq!(drop_braces("if (a) { b() }"), "if(a)b()");
+        assert_eq!(drop_braces("for (;;) { a() }"), "for(;;)a()");
+    }
+
+    #[test]
+    fn keeps_braces_that_matter() {
+        let drop_braces = |input| minify_with(input, |o| o.drop_braces = true);
+        assert_eq!(drop_braces("if (a) { let x = 1 }"), "if(a){let x=1}");
+        assert_eq!(
+            drop_braces("if (a) { if (b) c() } else d()"),
+            "if(a){if(b)c()}else d()"
+        );
+        assert_eq!(drop_braces("function f() { a() }"), "function f(){a()}");
+        assert_eq!(drop_braces("while (a) { }"), "while(a){}");
+    }
+
+    #[test]
+    fn keeps_braces_around_function_declarations() {
+        let drop_braces = |input| minify_with(input, |o| o.drop_braces = true);
+        assert_eq!(
+            drop_braces("if (a) { function f() {} }"),
+            "if(a){function f(){}}"
+        );
+        assert_eq!(
+            drop_braces("if (a) { async function f() {} }"),
+            "if(a){async function f(){}}"
+        );
+        assert_eq!(
+            drop_braces("if (a) { l: function f() {} }"),
+            "if(a){l:function f(){}}"
+        );
+        assert_eq!(
+            drop_braces("if (a) { l: m: function f() {} }"),
+            "if(a){l:m:function f(){}}"
+        );
+        assert_eq!(
+            drop_braces("if (a) { x = function () {} }"),
+            "if(a)x=function(){}"
+        );
+        assert_eq!(
+            drop_braces("if (a) { x = b ? c : function () {} }"),
+            "if(a)x=b?c:function(){}"
+        );
+    }
+}
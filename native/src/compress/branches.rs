@@ -0,0 +1,264 @@
+use super::evaluate::evaluate;
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, Token};
+use crate::util;
+
+/// Collapses logical expressions, conditionals and `if` statements whose
+/// condition is a constant down to the branch that runs.
+pub fn remove_dead_branches(tokens: &mut TokenBuffer) {
+    fold_logical(tokens);
+    fold_conditionals(tokens);
+    fold_ifs(tokens);
+}
+
+/// Whether `start..end` is wrapped in parentheses that are called right
+/// away, like `(0 || a.b)()`. Unwrapping the member expression would change
+/// what `this` is inside the call.
+fn is_parenthesized_callee(tokens: &[Token], start: usize, end: usize) -> bool {
+    start > 0
+        && tokens[start - 1] == Token::LeftParen
+        && tokens.get(end) == Some(&Token::RightParen)
+        && match tokens.get(end + 1) {
+            Some(Token::LeftParen) => true,
+            Some(Token::STRING(string)) => string.to_string().starts_with('`'),
+            _ => false,
+        }
+}
+
+/// Replaces the expression in `start..end` with the tokens in `kept`.
+fn replace_expression(tokens: &mut TokenBuffer, start: usize, end: usize, kept: Vec<Token>) {
+    let mut replacement = kept;
+    if util::is_statement_start(tokens, start) && util::needs_statement_parens(&replacement) {
+        replacement.insert(0, Token::LeftParen);
+        replacement.push(Token::RightParen);
+    }
+    tokens.splice(start..end, replacement);
+}
+
+/// Folds `&&`, `||` and `??` with a constant left operand.
+fn fold_logical(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let prec = match &tokens[i] {
+            Token::Operator(Operator::LogicalAnd) => precedence::LOGICAL_AND,
+            Token::Operator(Operator::LogicalOr) | Token::Operator(Operator::NullishCoalescing) => {
+                precedence::LOGICAL_OR
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let start = util::operand_start(tokens, i, prec);
+        let left = match evaluate(&tokens[start..i]) {
+            Some(left) => left,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let end = util::operand_end(tokens, i + 1, prec + 1);
+        let keep_left = match &tokens[i] {
+            Token::Operator(Operator::LogicalAnd) => !left.is_truthy(),
+            Token::Operator(Operator::LogicalOr) => left.is_truthy(),
+            _ => !left.is_nullish(),
+        };
+        if keep_left {
+            let kept = tokens[start..i].to_vec();
+            replace_expression(tokens, start, end, kept);
+        } else if !is_parenthesized_callee(tokens, start, end) {
+            let kept = tokens[i + 1..end].to_vec();
+            replace_expression(tokens, start, end, kept);
+        } else {
+            i += 1;
+            continue;
+        }
+        i = start;
+    }
+}
+
+/// Walks a branch of a conditional expression from `from`, returning where
+/// it ends. Nested conditionals are skipped, and the consequent stops at its
+/// `:`.
+fn conditional_branch_end(tokens: &[Token], from: usize) -> usize {
+    let mut pending = 0;
+    let mut i = from;
+    loop {
+        let end = util::operand_end(tokens, i, precedence::LOGICAL_OR);
+        match tokens.get(end) {
+            Some(Token::Operator(Operator::Ternary)) => pending += 1,
+            Some(Token::Operator(Operator::Colon)) if pending > 0 => pending -= 1,
+            Some(Token::Operator(op)) if op.is_assignment() || *op == Operator::Arrow => {}
+            _ => return end,
+        }
+        i = end + 1;
+    }
+}
+
+/// Folds `test ? a : b` with a constant test.
+fn fold_conditionals(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != Token::Operator(Operator::Ternary) {
+            i += 1;
+            continue;
+        }
+        let start = util::operand_start(tokens, i, precedence::LOGICAL_OR);
+        let test = match evaluate(&tokens[start..i]) {
+            Some(test) => test,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let colon = conditional_branch_end(tokens, i + 1);
+        if tokens.get(colon) != Some(&Token::Operator(Operator::Colon)) {
+            i += 1;
+            continue;
+        }
+        let end = conditional_branch_end(tokens, colon + 1);
+        let kept = if test.is_truthy() {
+            i + 1..colon
+        } else {
+            colon + 1..end
+        };
+        if is_parenthesized_callee(tokens, start, end) {
+            i += 1;
+            continue;
+        }
+        let kept = tokens[kept].to_vec();
+        replace_expression(tokens, start, end, kept);
+        i = start;
+    }
+}
+
+/// Folds `if` statements with a constant condition. `var` and function
+/// declarations in the removed branch still create their bindings, so they
+/// are kept as a bare `var` declaration.
+fn fold_ifs(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != Token::Keyword(Keyword::IF) || tokens.get(i + 1) != Some(&Token::LeftParen)
+        {
+            i += 1;
+            continue;
+        }
+        let close = match util::matching_close(tokens, i + 1) {
+            Some(close) => close,
+            None => break,
+        };
+        let test = match evaluate(&tokens[i + 2..close]) {
+            Some(test) => test,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let then_end = util::statement_end(tokens, close + 1);
+        let then_branch = close + 1..then_end;
+        let (else_branch, end) = match tokens.get(then_end) {
+            Some(Token::Keyword(Keyword::ELSE)) => {
+                let else_end = util::statement_end(tokens, then_end + 1);
+                (Some(then_end + 1..else_end), else_end)
+            }
+            _ => (None, then_end),
+        };
+        let (live, dead) = if test.is_truthy() {
+            (Some(then_branch), else_branch)
+        } else {
+            (else_branch, Some(then_branch))
+        };
+
+        let body_position = util::is_body_position(tokens, i);
+        let mut replacement = Vec::new();
+        if let Some(dead) = dead {
+            let names = util::var_declarations(&tokens[dead]);
+            if !names.is_empty() {
+                replacement.push(Token::Keyword(Keyword::VAR));
+                for (n, name) in names.into_iter().enumerate() {
+                    if n > 0 {
+                        replacement.push(Token::Comma);
+                    }
+                    replacement.push(Token::Ident(name));
+                }
+                replacement.push(Token::Semicolon);
+            }
+        }
+        if let Some(live) = live {
+            let body = &tokens[live];
+            let is_block = body.first() == Some(&Token::LeftBracket)
+                && util::matching_close(body, 0) == Some(body.len() - 1);
+            // a string at the start of the block could become a directive
+            let starts_with_string = matches!(body.get(1), Some(Token::STRING(_)));
+            if is_block
+                && !body_position
+                && !starts_with_string
+                && !util::has_lexical_declaration(&body[1..body.len() - 1])
+            {
+                let statements = &body[1..body.len() - 1];
+                replacement.extend_from_slice(statements);
+                if !statements.is_empty() && statements.last() != Some(&Token::Semicolon) {
+                    replacement.push(Token::Semicolon);
+                }
+            } else {
+                let wrap = body_position && !replacement.is_empty();
+                if wrap {
+                    replacement.insert(0, Token::LeftBracket);
+                }
+                replacement.extend_from_slice(body);
+                if wrap {
+                    replacement.push(Token::RightBracket);
+                }
+            }
+        }
+        if replacement.is_empty() && body_position {
+            replacement.push(Token::Semicolon);
+        }
+        tokens.splice(i..end, replacement);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn removes_constant_branches() {
+        let remove_dead_branches = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(remove_dead_branches("if (true) a(); else b()"), "a()");
+        assert_eq!(remove_dead_branches("if (0) { a() } else { b() }"), "b()");
+        assert_eq!(remove_dead_branches("if (\"\") a()"), "");
+        assert_eq!(remove_dead_branches("false ? a : b"), "b");
+    }
+
+    #[test]
+    fn keeps_unknown_conditions() {
+        let remove_dead_branches = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(
+            remove_dead_branches("if (x) a(); else b()"),
+            "if(x)a();else b()"
+        );
+    }
+
+    #[test]
+    fn keeps_blocks_with_declarations() {
+        let remove_dead_branches = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(remove_dead_branches("if (1) { let x = 1 }"), "{let x=1}");
+        assert_eq!(
+            remove_dead_branches("if (1) { async function f() {} }"),
+            "{async function f(){}}"
+        );
+        assert_eq!(
+            remove_dead_branches("if (1) { l: function f() {} }"),
+            "{l:function f(){}}"
+        );
+        assert_eq!(
+            remove_dead_branches("if (0) { l: function f() {} }"),
+            "var f"
+        );
+        assert_eq!(
+            remove_dead_branches("if (0) { var o = {a: function f() {}} }"),
+            "var o"
+        );
+    }
+}
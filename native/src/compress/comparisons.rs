@@ -0,0 +1,131 @@
+use super::evaluate::{evaluate, Value};
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, StringObject, Token};
+use crate::util;
+
+/// Shortens equality checks: `===` and `!==` become `==` and `!=` when both
+/// sides are known to have the same type, like in `typeof x==="string"`, and
+/// `typeof x=="undefined"` becomes `typeof x>"u"`.
+pub fn shorten_comparisons(tokens: &mut TokenBuffer) {
+    for i in 0..tokens.len() {
+        let negated = match &tokens[i] {
+            Token::Operator(Operator::Equal) | Token::Operator(Operator::StrictEqual) => false,
+            Token::Operator(Operator::NotEqual) | Token::Operator(Operator::StrictNotEqual) => true,
+            _ => continue,
+        };
+        let (start, end) = match operands(tokens, i) {
+            Some(operands) => operands,
+            None => continue,
+        };
+        let left = &tokens[start..i];
+        let right = &tokens[i + 1..end];
+        // every type name but "undefined" sorts before "u"
+        let replacement = if is_typeof(left) && is_undefined_string(right) {
+            let op = if negated {
+                Operator::LessThan
+            } else {
+                Operator::GreaterThan
+            };
+            vec![Token::Operator(op), shorten_to_u(&right[0])]
+        } else if is_undefined_string(left) && is_typeof(right) {
+            let op = if negated {
+                Operator::GreaterThan
+            } else {
+                Operator::LessThan
+            };
+            tokens.splice(i - 1..i, vec![shorten_to_u(&left[0])]);
+            vec![Token::Operator(op)]
+        } else if matches!(operand_type(left), Some(t) if Some(t) == operand_type(right)) {
+            let op = if negated {
+                Operator::NotEqual
+            } else {
+                Operator::Equal
+            };
+            vec![Token::Operator(op)]
+        } else {
+            continue;
+        };
+        let len = replacement.len();
+        tokens.splice(i..i + len, replacement);
+    }
+}
+
+/// Where the operands of the equality operator at `i` start and end. `a==b==c`
+/// compares `a==b` to `c`, so the operator has to be the first in a chain.
+fn operands(tokens: &[Token], i: usize) -> Option<(usize, usize)> {
+    let start = util::operand_start(tokens, i, precedence::EQUALITY + 1);
+    let end = util::operand_end(tokens, i + 1, precedence::EQUALITY + 1);
+    if start == i || end == i + 1 {
+        return None;
+    }
+    let chained = start > 0
+        && util::is_binary_at(tokens, start - 1)
+        && tokens[start - 1].binary_precedence() == Some(precedence::EQUALITY);
+    if chained {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/// Whether `expr` is a single `typeof` expression, like `typeof a.b`.
+fn is_typeof(expr: &[Token]) -> bool {
+    is_unary(expr, &Token::Keyword(Keyword::TYPEOF))
+}
+
+fn is_unary(expr: &[Token], operator: &Token) -> bool {
+    expr.first() == Some(operator) && util::operand_end(expr, 1, precedence::UNARY) == expr.len()
+}
+
+fn is_undefined_string(expr: &[Token]) -> bool {
+    matches!(expr, [Token::STRING(string)] if string.value == "undefined")
+}
+
+/// A copy of the string literal `tok`, in the same quotes, holding `u`.
+fn shorten_to_u(tok: &Token) -> Token {
+    match tok {
+        Token::STRING(string) => Token::STRING(StringObject {
+            value: "u".to_string(),
+            t: string.t.clone(),
+        }),
+        tok => tok.clone(),
+    }
+}
+
+/// The type of the value `expr` produces, when it can be told from the
+/// tokens alone. `null` gets a type of its own, unlike with `typeof`.
+fn operand_type(expr: &[Token]) -> Option<&'static str> {
+    if is_typeof(expr) {
+        return Some("string");
+    }
+    if is_unary(expr, &Token::Operator(Operator::Bang)) {
+        return Some("boolean");
+    }
+    match evaluate(expr)? {
+        Value::Null => Some("null"),
+        value => Some(value.type_of()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn loosens_comparisons_of_the_same_type() {
+        let shorten_comparisons = |input| minify_with(input, |o| o.comparisons = true);
+        assert_eq!(
+            shorten_comparisons("typeof a === \"string\""),
+            "typeof a==\"string\""
+        );
+        assert_eq!(shorten_comparisons("\"a\" !== typeof b"), "\"a\"!=typeof b");
+        assert_eq!(shorten_comparisons("!0 === !a"), "!0==!a");
+    }
+
+    #[test]
+    fn keeps_strict_comparisons_of_unknown_types() {
+        let shorten_comparisons = |input| minify_with(input, |o| o.comparisons = true);
+        assert_eq!(shorten_comparisons("a === 1"), "a===1");
+        assert_eq!(shorten_comparisons("a === b"), "a===b");
+    }
+}
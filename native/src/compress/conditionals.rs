@@ -0,0 +1,239 @@
+use std::ops::Range;
+
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, Token};
+use crate::util;
+
+/// Turns `if` statements whose branches are expressions into `&&`, `||` or
+/// `?:` expressions, like `if(a)b()` into `a&&b()`, and two returns into one,
+/// like `if(a)return x;return y` into `return a?x:y`.
+pub fn ifs_to_expressions(tokens: &mut TokenBuffer) {
+    // inner statements go first, so their `if`s are gone by the time the
+    // one around them is looked at
+    let mut i = tokens.len();
+    while i > 0 {
+        i -= 1;
+        if tokens[i] != Token::Keyword(Keyword::IF) || tokens.get(i + 1) != Some(&Token::LeftParen)
+        {
+            continue;
+        }
+        let close = match util::matching_close(tokens, i + 1) {
+            Some(close) => close,
+            None => continue,
+        };
+        let test = i + 2..close;
+        let then_end = util::statement_end(tokens, close + 1);
+        let then_branch = close + 1..then_end;
+        let else_branch = match tokens.get(then_end) {
+            Some(Token::Keyword(Keyword::ELSE)) => {
+                Some(then_end + 1..util::statement_end(tokens, then_end + 1))
+            }
+            _ => None,
+        };
+        let end = else_branch.as_ref().map_or(then_end, |branch| branch.end);
+        // the statement after an `if` that is the body of another one isn't
+        // part of that body
+        let returns = if util::is_body_position(tokens, i) && else_branch.is_none() {
+            None
+        } else {
+            returns(tokens, &test, &then_branch, &else_branch, end)
+        };
+        if let Some((end, replacement)) = returns {
+            tokens.splice(i..end, replacement);
+        } else if let Some(replacement) = expressions(tokens, &test, &then_branch, &else_branch) {
+            tokens.splice(i..end, replacement);
+        }
+    }
+}
+
+/// `if(a)b();else c()` as `a?b():c()`, and `if(a)b()` as `a&&b()`.
+fn expressions(
+    tokens: &[Token],
+    test: &Range<usize>,
+    then_branch: &Range<usize>,
+    else_branch: &Option<Range<usize>>,
+) -> Option<Vec<Token>> {
+    let consequent = expression_statement(tokens, then_branch.clone())?;
+    let (negated, test) = negation(&tokens[test.clone()]);
+    let mut replacement = Vec::new();
+    match else_branch {
+        Some(else_branch) => {
+            let alternate = expression_statement(tokens, else_branch.clone())?;
+            let (consequent, alternate) = if negated {
+                (alternate, consequent)
+            } else {
+                (consequent, alternate)
+            };
+            replacement.extend(wrap(test, precedence::LOGICAL_OR));
+            replacement.push(Token::Operator(Operator::Ternary));
+            replacement.extend(wrap(&tokens[consequent], precedence::ASSIGNMENT));
+            replacement.push(Token::Operator(Operator::Colon));
+            replacement.extend(wrap(&tokens[alternate], precedence::ASSIGNMENT));
+        }
+        None => {
+            let (op, prec) = if negated {
+                (Operator::LogicalOr, precedence::LOGICAL_OR)
+            } else {
+                (Operator::LogicalAnd, precedence::LOGICAL_AND)
+            };
+            // `??` can't be mixed with `&&` or `||` without parentheses
+            let operand = |expr: &[Token]| {
+                let nullish = util::has_top_level(expr, |tok| {
+                    *tok == Token::Operator(Operator::NullishCoalescing)
+                });
+                wrap(expr, if nullish { precedence::MEMBER } else { prec })
+            };
+            replacement.extend(operand(test));
+            replacement.push(Token::Operator(op));
+            replacement.extend(operand(&tokens[consequent]));
+        }
+    }
+    if util::needs_statement_parens(&replacement) {
+        replacement.insert(0, Token::LeftParen);
+        replacement.push(Token::RightParen);
+    }
+    replacement.push(Token::Semicolon);
+    Some(replacement)
+}
+
+/// `if(a)return x;return y` and `if(a)return x;else return y` as
+/// `return a?x:y`, along with where the statements it replaces end.
+fn returns(
+    tokens: &[Token],
+    test: &Range<usize>,
+    then_branch: &Range<usize>,
+    else_branch: &Option<Range<usize>>,
+    end: usize,
+) -> Option<(usize, Vec<Token>)> {
+    let consequent = return_value(tokens, then_branch.clone())?;
+    let (alternate, end) = match else_branch {
+        Some(else_branch) => (return_value(tokens, else_branch.clone())?, end),
+        None => {
+            let next_end = util::statement_end(tokens, end);
+            (return_value(tokens, end..next_end)?, next_end)
+        }
+    };
+    let (negated, test) = negation(&tokens[test.clone()]);
+    let (consequent, alternate) = if negated {
+        (alternate, consequent)
+    } else {
+        (consequent, alternate)
+    };
+    let mut replacement = vec![Token::Keyword(Keyword::RETURN)];
+    replacement.extend(wrap(test, precedence::LOGICAL_OR));
+    replacement.push(Token::Operator(Operator::Ternary));
+    replacement.extend(wrap(&tokens[consequent], precedence::ASSIGNMENT));
+    replacement.push(Token::Operator(Operator::Colon));
+    replacement.extend(wrap(&tokens[alternate], precedence::ASSIGNMENT));
+    replacement.push(Token::Semicolon);
+    Some((end, replacement))
+}
+
+/// Splits a leading `!` off of `test`, when it applies to the whole test.
+fn negation(test: &[Token]) -> (bool, &[Token]) {
+    if test.first() == Some(&Token::Operator(Operator::Bang))
+        && util::operand_end(test, 1, precedence::UNARY) == test.len()
+    {
+        (true, &test[1..])
+    } else {
+        (false, test)
+    }
+}
+
+/// `expr` as an operand of an operator with precedence `prec`, wrapped in
+/// parentheses when it binds more loosely.
+fn wrap(expr: &[Token], prec: u8) -> Vec<Token> {
+    let mut wrapped = expr.to_vec();
+    if util::expression_precedence(expr) < prec {
+        wrapped.insert(0, Token::LeftParen);
+        wrapped.push(Token::RightParen);
+    }
+    wrapped
+}
+
+/// The expression of the statement in `range`, when it is a single
+/// expression statement or a block holding only one.
+fn expression_statement(tokens: &[Token], range: Range<usize>) -> Option<Range<usize>> {
+    let range = single_statement(tokens, range)?;
+    let first = tokens.get(range.start)?;
+    let is_expression = match first {
+        Token::Keyword(keyword) => matches!(
+            keyword,
+            Keyword::THIS
+                | Keyword::SUPER
+                | Keyword::TRUE
+                | Keyword::FALSE
+                | Keyword::NULL
+                | Keyword::TYPEOF
+                | Keyword::VOID
+                | Keyword::DELETE
+                | Keyword::AWAIT
+                | Keyword::NEW
+        ),
+        Token::Ident(_) => tokens.get(range.start + 1) != Some(&Token::Operator(Operator::Colon)),
+        Token::LeftBracket | Token::Semicolon => false,
+        _ => true,
+    };
+    if !is_expression || util::operand_end(tokens, range.start, precedence::COMMA) != range.end {
+        return None;
+    }
+    Some(range)
+}
+
+/// The value of the `return` statement in `range`, or of the one in a block
+/// holding only it.
+fn return_value(tokens: &[Token], range: Range<usize>) -> Option<Range<usize>> {
+    let range = single_statement(tokens, range)?;
+    if tokens.get(range.start) != Some(&Token::Keyword(Keyword::RETURN)) {
+        return None;
+    }
+    let value = range.start + 1..range.end;
+    if value.is_empty() || util::operand_end(tokens, value.start, precedence::COMMA) != value.end {
+        return None;
+    }
+    Some(value)
+}
+
+/// The tokens of the statement in `range` without its semicolon, looking
+/// inside a block that holds only that statement.
+fn single_statement(tokens: &[Token], range: Range<usize>) -> Option<Range<usize>> {
+    let mut range = range;
+    if tokens.get(range.start) == Some(&Token::LeftBracket)
+        && util::matching_close(tokens, range.start) == Some(range.end - 1)
+    {
+        let inner = range.start + 1..range.end - 1;
+        if inner.is_empty() || util::statement_end(tokens, inner.start) != inner.end {
+            return None;
+        }
+        range = inner;
+    }
+    if range.end > range.start && tokens[range.end - 1] == Token::Semicolon {
+        range.end -= 1;
+    }
+    if range.is_empty() {
+        None
+    } else {
+        Some(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn turns_ifs_into_expressions() {
+        let conditionals = |input| minify_with(input, |o| o.conditionals = true);
+        assert_eq!(conditionals("if (a) b()"), "a&&b()");
+        assert_eq!(conditionals("if (!a) b()"), "a||b()");
+        assert_eq!(conditionals("if (a) b(); else c()"), "a?b():c()");
+        assert_eq!(conditionals("if (a) b = 1; else b = 2"), "a?b=1:b=2");
+    }
+
+    #[test]
+    fn keeps_ifs_around_statements() {
+        let conditionals = |input| minify_with(input, |o| o.conditionals = true);
+        assert_eq!(conditionals("if (a) { b(); c() }"), "if(a){b();c()}");
+        assert_eq!(conditionals("if (a) return; b()"), "if(a)return;b()");
+    }
+}
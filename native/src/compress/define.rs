@@ -0,0 +1,210 @@
+use crate::buffer::TokenBuffer;
+use crate::lexer::Lexer;
+use crate::mangle;
+use crate::token::{Operator, Token};
+use crate::util;
+
+struct Define {
+    chain: Vec<String>,
+    value: Vec<Token>,
+}
+
+impl Define {
+    fn new(name: &str, value: &str) -> Define {
+        let chain = name
+            .split('.')
+            .map(|part| part.trim().to_string())
+            .collect();
+        let mut value: Vec<Token> = Lexer::new(value)
+            .filter(|tok| *tok != Token::Semicolon)
+            .collect();
+        if !is_primary(&value) {
+            value.insert(0, Token::LeftParen);
+            value.push(Token::RightParen);
+        }
+        Define { chain, value }
+    }
+
+    /// Matches the identifier chain at `start`, returning where it ends.
+    fn matches(&self, tokens: &[Token], start: usize) -> Option<usize> {
+        let mut i = start;
+        for (n, part) in self.chain.iter().enumerate() {
+            if n > 0 {
+                if tokens.get(i) != Some(&Token::Operator(Operator::Period)) {
+                    return None;
+                }
+                i += 1;
+            }
+            match tokens.get(i) {
+                Some(Token::Ident(name)) if name == part => i += 1,
+                _ => return None,
+            }
+        }
+        Some(i)
+    }
+}
+
+/// Whether `value` can replace an identifier without parentheses, which is
+/// the case for a single token or a chain like `window.location`.
+fn is_primary(value: &[Token]) -> bool {
+    value.iter().enumerate().all(|(i, tok)| match tok {
+        Token::Ident(_) => i % 2 == 0,
+        Token::Operator(Operator::Period) => i % 2 == 1,
+        _ => value.len() == 1,
+    })
+}
+
+/// Whether the identifier chain in `start..end`, which starts with a global,
+/// is read as a value rather than being an assignment target.
+fn is_replaceable(tokens: &[Token], start: usize, end: usize) -> bool {
+    let prev = if start > 0 {
+        tokens.get(start - 1)
+    } else {
+        None
+    };
+    if let Some(Token::Operator(Operator::PlusPlus)) | Some(Token::Operator(Operator::MinusMinus)) =
+        prev
+    {
+        return false;
+    }
+    match tokens.get(end) {
+        Some(Token::Operator(op))
+            if op.is_assignment() || *op == Operator::PlusPlus || *op == Operator::MinusMinus =>
+        {
+            false
+        }
+        _ => !is_assignment_pattern(tokens, start),
+    }
+}
+
+/// Whether `i` is in the pattern of a destructuring assignment, like in
+/// `[a, {b: c}] = d`.
+fn is_assignment_pattern(tokens: &[Token], i: usize) -> bool {
+    let mut i = i;
+    while let Some(open) = util::enclosing_open(tokens, i) {
+        let literal = match tokens[open] {
+            Token::LeftBrace => open == 0 || !tokens[open - 1].ends_expression(),
+            Token::LeftBracket => util::is_object_literal(tokens, open),
+            _ => false,
+        };
+        if !literal {
+            return false;
+        }
+        match util::matching_close(tokens, open) {
+            Some(close) if tokens.get(close + 1) == Some(&Token::Operator(Operator::Assign)) => {
+                return true
+            }
+            Some(_) => i = open,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Replaces each global identifier chain named in `defines` with its value.
+/// Names declared in the code, and property keys, are left alone.
+pub fn substitute(tokens: &mut TokenBuffer, defines: &[(String, String)]) {
+    let mut defines: Vec<Define> = defines
+        .iter()
+        .map(|(name, value)| Define::new(name, value))
+        .collect();
+    // `process.env.NODE_ENV` has to win over `process.env`
+    defines.sort_by_key(|define| std::cmp::Reverse(define.chain.len()));
+
+    let globals = mangle::global_references(tokens);
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let matched = if globals.contains(&i) {
+            defines.iter().find_map(|define| {
+                let end = define.matches(tokens, i)?;
+                Some((define, end))
+            })
+        } else {
+            None
+        };
+        match matched {
+            Some((define, end)) if is_replaceable(tokens, i, end) => {
+                found.push((i, end, define));
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    for (start, end, define) in found.into_iter().rev() {
+        tokens.splice(start..end, define.value.iter().cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    /// The definitions the tests substitute.
+    fn definitions() -> Vec<(String, String)> {
+        vec![
+            ("__DEV__".to_string(), "false".to_string()),
+            (
+                "process.env.NODE_ENV".to_string(),
+                "\"production\"".to_string(),
+            ),
+            ("FLAG".to_string(), "a || b".to_string()),
+        ]
+    }
+
+    #[test]
+    fn replaces_global_chains() {
+        let define = |input| minify_with(input, |o| o.define = definitions());
+        assert_eq!(define("if (__DEV__) log()"), "if(false)log()");
+        assert_eq!(
+            define("x = process.env.NODE_ENV !== 'production'"),
+            "x=\"production\"!=='production'"
+        );
+        assert_eq!(define("x = FLAG.y"), "x=(a||b).y");
+        assert_eq!(define("x = typeof __DEV__"), "x=typeof false");
+    }
+
+    #[test]
+    fn keeps_keys_and_members() {
+        let define = |input| minify_with(input, |o| o.define = definitions());
+        assert_eq!(
+            define("x = {__DEV__: 1, a: __DEV__}"),
+            "x={__DEV__:1,a:false}"
+        );
+        assert_eq!(define("x = {__DEV__}"), "x={__DEV__}");
+        assert_eq!(define("x = {__DEV__() {}}"), "x={__DEV__(){}}");
+        assert_eq!(define("x = {FLAG() {}}"), "x={FLAG(){}}");
+        assert_eq!(define("class A { __DEV__() {} }"), "class A{__DEV__(){}}");
+        assert_eq!(define("x = a.__DEV__"), "x=a.__DEV__");
+    }
+
+    #[test]
+    fn keeps_bindings() {
+        let define = |input| minify_with(input, |o| o.define = definitions());
+        assert_eq!(define("function f(__DEV__) {}"), "function f(__DEV__){}");
+        assert_eq!(define("x = (__DEV__) => 1"), "x=(__DEV__)=>1");
+        assert_eq!(define("let [__DEV__] = x"), "let[__DEV__]=x");
+        assert_eq!(define("try {} catch (__DEV__) {}"), "try{}catch(__DEV__){}");
+        assert_eq!(
+            define("function f(a = __DEV__) { return __DEV__ }"),
+            "function f(a=false){return false}"
+        );
+        assert_eq!(
+            define("function f() { var __DEV__; return __DEV__ }"),
+            "function f(){var __DEV__;return __DEV__}"
+        );
+    }
+
+    #[test]
+    fn keeps_assignment_targets() {
+        let define = |input| minify_with(input, |o| o.define = definitions());
+        assert_eq!(define("__DEV__ = 1"), "__DEV__=1");
+        assert_eq!(define("__DEV__ ||= x"), "__DEV__||=x");
+        assert_eq!(define("__DEV__ ??= x"), "__DEV__??=x");
+        assert_eq!(
+            define("[__DEV__, {a: __DEV__}] = x"),
+            "[__DEV__,{a:__DEV__}]=x"
+        );
+        assert_eq!(define("x = [__DEV__]"), "x=[false]");
+    }
+}
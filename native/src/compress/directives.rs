@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, StringType, Token};
+use crate::util;
+
+/// Removes directives that are repeated in the same prologue, and
+/// `"use strict"` in functions that are already strict because of the code
+/// around them. Modules are always strict.
+pub fn dedupe_directives(tokens: &mut TokenBuffer, module: bool) {
+    let mut removed = Vec::new();
+    let program_strict = prologue(tokens, 0, module, &mut removed);
+    // the `}` of each function or class body we're in, and whether it is
+    // strict
+    let mut scopes: Vec<(usize, bool)> = Vec::new();
+    for i in 0..tokens.len() {
+        while scopes.last().is_some_and(|&(close, _)| close < i) {
+            scopes.pop();
+        }
+        if tokens[i] != Token::LeftBracket {
+            continue;
+        }
+        let strict = scopes.last().map_or(program_strict, |&(_, strict)| strict);
+        let close = match util::matching_close(tokens, i) {
+            Some(close) => close,
+            None => break,
+        };
+        if util::is_function_body(tokens, i) {
+            let strict = prologue(tokens, i + 1, strict, &mut removed);
+            scopes.push((close, strict));
+        } else if is_class_body(tokens, i) {
+            scopes.push((close, true));
+        }
+    }
+    for range in removed.into_iter().rev() {
+        tokens.drain(range);
+    }
+}
+
+/// Walks the directive prologue starting at `start`, adding the directives
+/// that can go to `removed`. Returns whether the code after it is strict.
+fn prologue(tokens: &[Token], start: usize, strict: bool, removed: &mut Vec<Range<usize>>) -> bool {
+    let mut strict = strict;
+    let mut seen: Vec<&str> = Vec::new();
+    let mut i = start;
+    while let Some(Token::STRING(string)) = tokens.get(i) {
+        if string.t == StringType::Backtick {
+            break;
+        }
+        // a string followed by anything else is an expression, like
+        // `"use strict".length`, and ends the prologue
+        let end = match tokens.get(i + 1) {
+            Some(Token::Semicolon) => i + 2,
+            None | Some(Token::RightBracket) => i + 1,
+            _ => break,
+        };
+        let use_strict = string.value == "use strict";
+        if (use_strict && strict) || seen.contains(&string.value.as_str()) {
+            removed.push(i..end);
+        } else {
+            seen.push(&string.value);
+        }
+        strict = strict || use_strict;
+        i = end;
+    }
+    strict
+}
+
+/// Whether the `{` at `open` starts the body of a class, whose code is
+/// always strict.
+fn is_class_body(tokens: &[Token], open: usize) -> bool {
+    let mut i = open;
+    while i > 0 {
+        i -= 1;
+        match &tokens[i] {
+            Token::Keyword(Keyword::CLASS) => return true,
+            Token::RightParen | Token::RightBrace => match util::matching_open(tokens, i) {
+                Some(open) => i = open,
+                None => return false,
+            },
+            Token::Ident(_)
+            | Token::Keyword(Keyword::EXTENDS)
+            | Token::Operator(Operator::Period) => {}
+            _ => return false,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_repeated_directives() {
+        let dedupe_directives = |input| minify_with(input, |o| o.directives = true);
+        assert_eq!(
+            dedupe_directives("\"use strict\"; \"use strict\"; a()"),
+            "\"use strict\";a()"
+        );
+        assert_eq!(
+            dedupe_directives("function f() { \"use strict\"; \"use strict\"; return 1 }"),
+            "function f(){\"use strict\";return 1}"
+        );
+        assert_eq!(
+            dedupe_directives("\"use strict\"; function f() { \"use strict\"; return 1 }"),
+            "\"use strict\";function f(){return 1}"
+        );
+    }
+
+    #[test]
+    fn drops_use_strict_in_modules() {
+        let module = |input| {
+            minify_with(input, |o| {
+                o.directives = true;
+                o.module = true;
+            })
+        };
+        assert_eq!(module("\"use strict\"; a()"), "a()");
+    }
+
+    #[test]
+    fn keeps_strings_from_becoming_directives() {
+        let dedupe_directives = |input| minify_with(input, |o| o.directives = true);
+        assert_eq!(dedupe_directives("\"a\"\n+b"), "\"a\"+b");
+    }
+}
@@ -0,0 +1,173 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, Token};
+use crate::util;
+
+/// Removes `debugger` statements.
+pub fn drop_debugger(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_debugger = tokens[i] == Token::Keyword(Keyword::DEBUGGER)
+            && is_statement(tokens, i)
+            && ends_statement(tokens.get(i + 1));
+        if is_debugger {
+            remove_statement(tokens, i, i + 1, Vec::new());
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Removes statements that only call one of `names`. Arguments that may have
+/// side effects are still evaluated, in their original order.
+pub fn drop_pure_funcs(tokens: &mut TokenBuffer, names: &[String]) {
+    let callees: Vec<Vec<&str>> = names.iter().map(|name| name.split('.').collect()).collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_statement(tokens, i) {
+            let call = callees
+                .iter()
+                .find_map(|callee| match_callee(tokens, i, callee))
+                .and_then(|open| Some((open, util::matching_close(tokens, open)?)));
+            if let Some((open, close)) = call {
+                if ends_statement(tokens.get(close + 1)) {
+                    let kept = side_effects(&tokens[open + 1..close]);
+                    remove_statement(tokens, i, close + 1, kept);
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Matches a dotted callee like `console.log` starting at `start`, returning
+/// the index of the `(` that follows it.
+fn match_callee(tokens: &[Token], start: usize, callee: &[&str]) -> Option<usize> {
+    let mut i = start;
+    for (n, part) in callee.iter().enumerate() {
+        if n > 0 {
+            if tokens.get(i) != Some(&Token::Operator(Operator::Period)) {
+                return None;
+            }
+            i += 1;
+        }
+        match tokens.get(i) {
+            Some(Token::Ident(name)) if name == part => {}
+            Some(Token::Keyword(Keyword::THIS)) if *part == "this" => {}
+            _ => return None,
+        }
+        i += 1;
+    }
+    match tokens.get(i) {
+        Some(Token::LeftParen) => Some(i),
+        _ => None,
+    }
+}
+
+/// Whether a statement can begin at `i`. Unlike `util::is_statement_start`,
+/// this leaves out the first key of an object literal, like in
+/// `{debugger:1}`.
+fn is_statement(tokens: &[Token], i: usize) -> bool {
+    let is_key =
+        i > 0 && tokens[i - 1] == Token::LeftBracket && util::is_object_literal(tokens, i - 1);
+    util::is_statement_start(tokens, i) && !is_key
+}
+
+fn ends_statement(tok: Option<&Token>) -> bool {
+    matches!(
+        tok,
+        None | Some(Token::Semicolon) | Some(Token::RightBracket)
+    )
+}
+
+/// Builds an expression statement out of the arguments in `args` that have
+/// to be kept for their side effects.
+fn side_effects(args: &[Token]) -> Vec<Token> {
+    let mut kept = Vec::new();
+    for range in util::split_top_level(args) {
+        let arg = &args[range];
+        if util::is_side_effect_free(arg) {
+            continue;
+        }
+        if !kept.is_empty() {
+            kept.push(Token::Comma);
+        }
+        if arg.first() == Some(&Token::Operator(Operator::Spread)) {
+            // Spreading still runs the iterator, so keep it inside an array.
+            kept.push(Token::LeftBrace);
+            kept.extend_from_slice(arg);
+            kept.push(Token::RightBrace);
+        } else {
+            kept.extend_from_slice(arg);
+        }
+    }
+    if util::needs_statement_parens(&kept) {
+        kept.insert(0, Token::LeftParen);
+        kept.push(Token::RightParen);
+    }
+    kept
+}
+
+/// Replaces the statement in `start..end` with `replacement`, taking its
+/// semicolon with it unless the statement is the body of another one.
+fn remove_statement(tokens: &mut TokenBuffer, start: usize, end: usize, replacement: Vec<Token>) {
+    let has_semicolon = tokens.get(end) == Some(&Token::Semicolon);
+    if !replacement.is_empty() {
+        tokens.splice(start..end, replacement);
+    } else if util::is_body_position(tokens, start) {
+        if has_semicolon {
+            tokens.drain(start..end);
+        } else {
+            tokens.splice(start..end, vec![Token::Semicolon]);
+        }
+    } else if has_semicolon {
+        tokens.drain(start..end + 1);
+    } else {
+        tokens.drain(start..end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_debugger_statements() {
+        let drop_debugger = |input| minify_with(input, |o| o.drop_debugger = true);
+        assert_eq!(drop_debugger("a();\ndebugger;\nb()"), "a();b()");
+        assert_eq!(drop_debugger("if (a) debugger; else b()"), "if(a);else b()");
+        assert_eq!(drop_debugger("function f() { debugger }"), "function f(){}");
+    }
+
+    #[test]
+    fn drops_pure_calls() {
+        let drop_pure_funcs = |input| {
+            minify_with(input, |o| {
+                o.pure_funcs = vec!["console.log".to_string(), "assert".to_string()]
+            })
+        };
+        assert_eq!(drop_pure_funcs("console.log('a', 1, x); f()"), "f()");
+        assert_eq!(drop_pure_funcs("if (a) assert(b)"), "if(a);");
+        assert_eq!(drop_pure_funcs("console.log(f(), g())"), "f(),g()");
+        assert_eq!(drop_pure_funcs("x = console.log(a)"), "x=console.log(a)");
+    }
+
+    #[test]
+    fn keeps_debugger_as_a_key() {
+        let drop_debugger = |input| minify_with(input, |o| o.drop_debugger = true);
+        assert_eq!(drop_debugger("a = {debugger: 1}"), "a={debugger:1}");
+        assert_eq!(
+            drop_debugger("class A { debugger() {} }"),
+            "class A{debugger(){}}"
+        );
+    }
+
+    #[test]
+    fn keeps_arguments_that_may_have_side_effects() {
+        let drop_pure_funcs =
+            |input| minify_with(input, |o| o.pure_funcs = vec!["console.log".to_string()]);
+        assert_eq!(drop_pure_funcs("console.log(obj.getter)"), "obj.getter");
+        assert_eq!(drop_pure_funcs("console.log(a + b, -1)"), "a+b");
+        assert_eq!(drop_pure_funcs("console.log(typeof a.b)"), "typeof a.b");
+    }
+}
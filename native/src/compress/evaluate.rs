@@ -0,0 +1,473 @@
+use crate::token::{precedence, Keyword, Operator, StringType, Token};
+
+/// A primitive JavaScript value that an expression was reduced to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Undefined | Value::Null => false,
+            Value::Bool(value) => *value,
+            Value::Number(value) => *value != 0.0 && !value.is_nan(),
+            Value::Str(value) => !value.is_empty(),
+        }
+    }
+
+    pub fn is_nullish(&self) -> bool {
+        matches!(self, Value::Undefined | Value::Null)
+    }
+
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            Value::Undefined => "undefined",
+            Value::Null => "object",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+        }
+    }
+
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Value::Undefined => f64::NAN,
+            Value::Null => 0.0,
+            Value::Bool(value) => *value as u8 as f64,
+            Value::Number(value) => *value,
+            Value::Str(value) => string_to_number(value),
+        }
+    }
+
+    pub fn to_js_string(&self) -> String {
+        match self {
+            Value::Undefined => "undefined".to_string(),
+            Value::Null => "null".to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::Number(value) => number_to_string(*value),
+            Value::Str(value) => value.clone(),
+        }
+    }
+
+    /// `==`, with its conversions between primitives.
+    fn loose_equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (a, b) if a.is_nullish() || b.is_nullish() => a.is_nullish() && b.is_nullish(),
+            (Value::Bool(_), _)
+            | (_, Value::Bool(_))
+            | (Value::Number(_), Value::Str(_))
+            | (Value::Str(_), Value::Number(_)) => self.to_number() == other.to_number(),
+            _ => self == other,
+        }
+    }
+}
+
+fn is_js_whitespace(ch: char) -> bool {
+    ch.is_whitespace() || ch == '\u{feff}'
+}
+
+/// `Number(string)`, which is stricter than Rust's float parsing about what
+/// it accepts.
+pub fn string_to_number(string: &str) -> f64 {
+    let string = string.trim_matches(is_js_whitespace);
+    if string.is_empty() {
+        return 0.0;
+    }
+    let radix = match string.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        return match u64::from_str_radix(&string[2..], radix) {
+            Ok(value) => value as f64,
+            Err(_) => f64::NAN,
+        };
+    }
+    let unsigned = string.trim_start_matches(['+', '-']);
+    if unsigned == "Infinity" && string.len() - unsigned.len() <= 1 {
+        return if string.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    let valid = string
+        .chars()
+        .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '+' | '-'));
+    if !valid {
+        return f64::NAN;
+    }
+    string.parse().unwrap_or(f64::NAN)
+}
+
+/// The significant digits of a finite, positive number and the position of
+/// the decimal point relative to them, so `1.5` is `("15", 1)` and `0.01` is
+/// `("1", -1)`.
+pub fn decimal_digits(value: f64) -> (String, i32) {
+    // Rust prints the shortest digits that read back as the same number,
+    // which is what `Number.prototype.toString` is specified to use as well.
+    let formatted = format!("{:e}", value);
+    let (mantissa, exponent) = formatted.split_at(formatted.find('e').unwrap());
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    (digits, exponent + 1)
+}
+
+/// `String(number)`.
+pub fn number_to_string(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if value < 0.0 {
+        return format!("-{}", number_to_string(-value));
+    }
+    if value.is_infinite() {
+        return "Infinity".to_string();
+    }
+    let (digits, n) = decimal_digits(value);
+    let k = digits.len() as i32;
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let exponent = n - 1;
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let fraction = if k == 1 {
+            String::new()
+        } else {
+            format!(".{}", &digits[1..])
+        };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, exponent.abs())
+    }
+}
+
+fn to_int32(value: f64) -> i32 {
+    to_uint32(value) as i32
+}
+
+fn to_uint32(value: f64) -> u32 {
+    if !value.is_finite() {
+        return 0;
+    }
+    value.trunc().rem_euclid(4294967296.0) as u32
+}
+
+/// `a < b` between primitives, which is `None` when either side is `NaN`.
+fn less_than(a: &Value, b: &Value) -> Option<bool> {
+    if let (Value::Str(a), Value::Str(b)) = (a, b) {
+        // strings compare by UTF-16 code units, not by code points
+        return Some(a.encode_utf16().lt(b.encode_utf16()));
+    }
+    let (a, b) = (a.to_number(), b.to_number());
+    if a.is_nan() || b.is_nan() {
+        None
+    } else {
+        Some(a < b)
+    }
+}
+
+/// `a ** b`, which differs from `powf` for a base of one.
+fn power(base: f64, exponent: f64) -> f64 {
+    if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        base.powf(exponent)
+    }
+}
+
+/// The value of a numeric literal as written in the source.
+pub fn parse_number(literal: &str) -> Option<f64> {
+    // legacy octal literals like `017`
+    let mut digits = literal.chars();
+    if digits.next() == Some('0') && digits.next().is_some_and(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let value = string_to_number(literal);
+    if value.is_nan() || literal.starts_with(['+', '-']) {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn read_hex(chars: &mut std::iter::Peekable<std::str::Chars>, len: usize) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..len {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(value)
+}
+
+fn read_code_point(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<u32> {
+    if chars.peek() == Some(&'{') {
+        chars.next();
+        let mut value: u32 = 0;
+        loop {
+            match chars.next()? {
+                '}' => return Some(value),
+                ch => value = value.checked_mul(16)? + ch.to_digit(16)?,
+            }
+        }
+    }
+    read_hex(chars, 4)
+}
+
+/// Resolves the escape sequences in the source text of a string literal.
+/// Lone surrogates and legacy octal escapes give `None`, as they can't be
+/// carried through a Rust string unchanged.
+pub fn cook(raw: &str) -> Option<String> {
+    let mut cooked = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            cooked.push(ch);
+            continue;
+        }
+        let ch = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            '0' if !chars.peek().is_some_and(|ch| ch.is_ascii_digit()) => '\0',
+            '1'..='9' | '0' => return None,
+            'x' => std::char::from_u32(read_hex(&mut chars, 2)?)?,
+            'u' => {
+                let mut code = read_code_point(&mut chars)?;
+                if (0xd800..0xdc00).contains(&code) {
+                    if chars.next()? != '\\' || chars.next()? != 'u' {
+                        return None;
+                    }
+                    let low = read_code_point(&mut chars)?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return None;
+                    }
+                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                }
+                std::char::from_u32(code)?
+            }
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => continue,
+            ch => ch,
+        };
+        cooked.push(ch);
+    }
+    Some(cooked)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+/// Reduces `tokens` to a value, if they form an expression made only of
+/// literals.
+pub fn evaluate(tokens: &[Token]) -> Option<Value> {
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expression(precedence::COMMA)?;
+    if parser.pos == tokens.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, tok: &Token) -> Option<()> {
+        if self.peek()? == tok {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn expression(&mut self, min_prec: u8) -> Option<Value> {
+        let mut left = self.unary()?;
+        while let Some(tok) = self.peek() {
+            if *tok == Token::Operator(Operator::Colon) {
+                break;
+            }
+            let prec = match tok.binary_precedence() {
+                Some(prec) if prec >= min_prec => prec,
+                _ => break,
+            };
+            self.pos += 1;
+            if *tok == Token::Operator(Operator::Ternary) {
+                let consequent = self.expression(precedence::ASSIGNMENT)?;
+                self.expect(&Token::Operator(Operator::Colon))?;
+                let alternate = self.expression(precedence::CONDITIONAL)?;
+                left = if left.is_truthy() {
+                    consequent
+                } else {
+                    alternate
+                };
+                continue;
+            }
+            let right = if *tok == Token::Operator(Operator::Exponent) {
+                self.expression(prec)?
+            } else {
+                self.expression(prec + 1)?
+            };
+            left = binary(tok, left, right)?;
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Value> {
+        let value = match self.peek()? {
+            Token::Operator(Operator::Bang) => {
+                self.pos += 1;
+                Value::Bool(!self.unary()?.is_truthy())
+            }
+            Token::Operator(Operator::Minus) => {
+                self.pos += 1;
+                Value::Number(-self.unary()?.to_number())
+            }
+            Token::Operator(Operator::Plus) => {
+                self.pos += 1;
+                Value::Number(self.unary()?.to_number())
+            }
+            Token::Operator(Operator::BitwiseNot) => {
+                self.pos += 1;
+                Value::Number(!to_int32(self.unary()?.to_number()) as f64)
+            }
+            Token::Keyword(Keyword::TYPEOF) => {
+                self.pos += 1;
+                Value::Str(self.unary()?.type_of().to_string())
+            }
+            Token::Keyword(Keyword::VOID) => {
+                self.pos += 1;
+                self.unary()?;
+                Value::Undefined
+            }
+            _ => self.primary()?,
+        };
+        Some(value)
+    }
+
+    fn primary(&mut self) -> Option<Value> {
+        let value = match self.peek()? {
+            Token::NUMBER(number) => Value::Number(parse_number(number)?),
+            Token::STRING(string) => {
+                if string.t == StringType::Backtick && string.value.contains("${") {
+                    return None;
+                }
+                Value::Str(cook(&string.value)?)
+            }
+            Token::Keyword(Keyword::TRUE) => Value::Bool(true),
+            Token::Keyword(Keyword::FALSE) => Value::Bool(false),
+            Token::Keyword(Keyword::NULL) => Value::Null,
+            Token::Ident(name) => match name.as_str() {
+                "undefined" => Value::Undefined,
+                "NaN" => Value::Number(f64::NAN),
+                "Infinity" => Value::Number(f64::INFINITY),
+                _ => return None,
+            },
+            Token::LeftParen => {
+                self.pos += 1;
+                let value = self.expression(precedence::COMMA)?;
+                self.expect(&Token::RightParen)?;
+                return Some(value);
+            }
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(value)
+    }
+}
+
+fn binary(op: &Token, left: Value, right: Value) -> Option<Value> {
+    let value = match op {
+        Token::Comma => right,
+        Token::Operator(op) => match op {
+            Operator::Plus => match (&left, &right) {
+                (Value::Str(_), _) | (_, Value::Str(_)) => {
+                    Value::Str(left.to_js_string() + &right.to_js_string())
+                }
+                _ => Value::Number(left.to_number() + right.to_number()),
+            },
+            Operator::Minus => Value::Number(left.to_number() - right.to_number()),
+            Operator::Asterisk => Value::Number(left.to_number() * right.to_number()),
+            Operator::Slash => Value::Number(left.to_number() / right.to_number()),
+            Operator::Modulo => Value::Number(left.to_number() % right.to_number()),
+            Operator::Exponent => Value::Number(power(left.to_number(), right.to_number())),
+            Operator::BitwiseAnd => {
+                Value::Number((to_int32(left.to_number()) & to_int32(right.to_number())) as f64)
+            }
+            Operator::BitwiseOr => {
+                Value::Number((to_int32(left.to_number()) | to_int32(right.to_number())) as f64)
+            }
+            Operator::BitwiseXor => {
+                Value::Number((to_int32(left.to_number()) ^ to_int32(right.to_number())) as f64)
+            }
+            Operator::BitwiseLeft => {
+                let shift = to_uint32(right.to_number()) & 31;
+                Value::Number(to_int32(left.to_number()).wrapping_shl(shift) as f64)
+            }
+            Operator::BitwiseRight => {
+                let shift = to_uint32(right.to_number()) & 31;
+                Value::Number((to_int32(left.to_number()) >> shift) as f64)
+            }
+            Operator::UnsignedBitwiseRight => {
+                let shift = to_uint32(right.to_number()) & 31;
+                Value::Number((to_uint32(left.to_number()) >> shift) as f64)
+            }
+            Operator::LessThan => Value::Bool(less_than(&left, &right) == Some(true)),
+            Operator::GreaterThan => Value::Bool(less_than(&right, &left) == Some(true)),
+            Operator::LessThanEqual => Value::Bool(less_than(&right, &left) == Some(false)),
+            Operator::GreaterThanEqual => Value::Bool(less_than(&left, &right) == Some(false)),
+            Operator::StrictEqual => Value::Bool(left == right),
+            Operator::StrictNotEqual => Value::Bool(left != right),
+            Operator::Equal => Value::Bool(left.loose_equals(&right)),
+            Operator::NotEqual => Value::Bool(!left.loose_equals(&right)),
+            Operator::LogicalAnd => {
+                if left.is_truthy() {
+                    right
+                } else {
+                    left
+                }
+            }
+            Operator::LogicalOr => {
+                if left.is_truthy() {
+                    left
+                } else {
+                    right
+                }
+            }
+            Operator::NullishCoalescing => {
+                if left.is_nullish() {
+                    right
+                } else {
+                    left
+                }
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(value)
+}
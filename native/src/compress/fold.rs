@@ -0,0 +1,225 @@
+use super::evaluate::{decimal_digits, evaluate, number_to_string, Value};
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, StringObject, StringType, Token};
+use crate::util;
+
+/// Replaces operations on literals with their result, like `60*60*1000`
+/// with `36e5`, as long as the result is not longer than the expression.
+pub fn fold_constants(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let (start, end, prec) = match folded_range(tokens, i) {
+            Some(range) => range,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let value = match evaluate(&tokens[start..end]) {
+            // a string at the start of a statement could become a directive
+            Some(Value::Str(_)) if util::is_statement_start(tokens, start) => None,
+            value => value,
+        };
+        let replacement = value.map(|value| {
+            let (mut replacement, value_prec) = to_tokens(&value);
+            if value_prec < prec {
+                replacement.insert(0, Token::LeftParen);
+                replacement.push(Token::RightParen);
+            }
+            replacement
+        });
+        match replacement {
+            Some(replacement)
+                if replacement[..] != tokens[start..end]
+                    && printed_len(&replacement) <= printed_len(&tokens[start..end]) =>
+            {
+                tokens.splice(start..end, replacement);
+                i = start;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// The expression made up by the operator at `i` and its operands, along
+/// with the precedence it was parsed at.
+fn folded_range(tokens: &[Token], i: usize) -> Option<(usize, usize, u8)> {
+    match &tokens[i] {
+        Token::Keyword(Keyword::TYPEOF)
+        | Token::Operator(Operator::BitwiseNot)
+        | Token::Operator(Operator::Bang) => {
+            let end = util::operand_end(tokens, i + 1, precedence::UNARY);
+            Some((i, end, precedence::UNARY))
+        }
+        tok => {
+            let prec = tok.binary_precedence()?;
+            if prec < precedence::LOGICAL_OR || !util::is_binary_at(tokens, i) {
+                return None;
+            }
+            let start = util::operand_start(tokens, i, prec);
+            let end = if *tok == Token::Operator(Operator::Exponent) {
+                util::operand_end(tokens, i + 1, prec)
+            } else {
+                util::operand_end(tokens, i + 1, prec + 1)
+            };
+            if start == i || end == i + 1 {
+                return None;
+            }
+            Some((start, end, prec))
+        }
+    }
+}
+
+fn printed_len(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .map(|tok| tok.to_string().chars().count())
+        .sum()
+}
+
+/// The shortest tokens that produce `value`, and their precedence.
+fn to_tokens(value: &Value) -> (Vec<Token>, u8) {
+    match value {
+        Value::Undefined => (
+            vec![
+                Token::Keyword(Keyword::VOID),
+                Token::NUMBER("0".to_string()),
+            ],
+            precedence::UNARY,
+        ),
+        Value::Null => (vec![Token::Keyword(Keyword::NULL)], precedence::MEMBER),
+        Value::Bool(value) => {
+            let number = if *value { "0" } else { "1" };
+            (
+                vec![
+                    Token::Operator(Operator::Bang),
+                    Token::NUMBER(number.to_string()),
+                ],
+                precedence::UNARY,
+            )
+        }
+        Value::Number(value) if value.is_nan() => {
+            (vec![Token::Ident("NaN".to_string())], precedence::MEMBER)
+        }
+        Value::Number(value) => {
+            let mut tokens = Vec::new();
+            // `-0` keeps its sign, which `1/x` and `Object.is` can tell
+            if value.is_sign_negative() {
+                tokens.push(Token::Operator(Operator::Minus));
+            }
+            if value.is_infinite() {
+                tokens.push(Token::NUMBER("1".to_string()));
+                tokens.push(Token::Operator(Operator::Slash));
+                tokens.push(Token::NUMBER("0".to_string()));
+                return (tokens, precedence::MULTIPLICATIVE);
+            }
+            tokens.push(Token::NUMBER(number_literal(value.abs())));
+            let prec = if tokens.len() > 1 {
+                precedence::UNARY
+            } else {
+                precedence::MEMBER
+            };
+            (tokens, prec)
+        }
+        Value::Str(value) => (
+            vec![Token::STRING(string_literal(value))],
+            precedence::MEMBER,
+        ),
+    }
+}
+
+/// The shortest literal for a positive number, `.5` rather than `0.5` and
+/// `36e5` rather than `3600000`.
+fn number_literal(value: f64) -> String {
+    let plain = number_to_string(value);
+    if value == 0.0 {
+        return plain;
+    }
+    let plain = match plain.strip_prefix("0.") {
+        Some(fraction) => format!(".{}", fraction),
+        None => plain,
+    };
+    let (digits, n) = decimal_digits(value);
+    let exponent = n - digits.len() as i32;
+    let scientific = format!("{}e{}", digits, exponent);
+    if exponent != 0 && scientific.len() < plain.len() {
+        scientific
+    } else {
+        plain
+    }
+}
+
+/// A string literal for `value`, in whichever quotes need fewer escapes.
+fn string_literal(value: &str) -> StringObject {
+    let doubles = value.matches('"').count();
+    let singles = value.matches('\'').count();
+    let (quote, t) = if singles < doubles {
+        ('\'', StringType::SingleQuote)
+    } else {
+        ('"', StringType::DoubleQuote)
+    };
+    let mut escaped = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{b}' => escaped.push_str("\\v"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            // `\0` followed by a digit would read as an octal escape
+            '\0' if !chars.peek().is_some_and(|ch| ch.is_ascii_digit()) => escaped.push_str("\\0"),
+            ch if ch == quote => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            ch if ch < ' ' && ch != '\t' => escaped.push_str(&format!("\\x{:02x}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    StringObject { value: escaped, t }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn folds_arithmetic() {
+        let fold_constants = |input| minify_with(input, |o| o.fold_constants = true);
+        assert_eq!(fold_constants("x = 1 + 2 * 3"), "x=7");
+        assert_eq!(fold_constants("x = 2 ** 10"), "x=1024");
+        assert_eq!(fold_constants("x = 10 % 3 - -1"), "x=2");
+    }
+
+    #[test]
+    fn folds_strings() {
+        let fold_constants = |input| minify_with(input, |o| o.fold_constants = true);
+        assert_eq!(fold_constants("x = \"a\" + \"b\""), "x=\"ab\"");
+        assert_eq!(fold_constants("x = \"a\" + 1"), "x=\"a1\"");
+        assert_eq!(fold_constants("x = 'it\\'s' + \"a\""), "x=\"it'sa\"");
+    }
+
+    #[test]
+    fn keeps_inexact_and_unknown_values() {
+        let fold_constants = |input| minify_with(input, |o| o.fold_constants = true);
+        assert_eq!(fold_constants("x = 0.1 + 0.2"), "x=0.1+0.2");
+        assert_eq!(fold_constants("x = 1 / 0"), "x=1/0");
+        assert_eq!(fold_constants("x = a + 1 + 2"), "x=a+1+2");
+    }
+
+    #[test]
+    fn keeps_the_sign_of_zero() {
+        let fold_constants = |input| minify_with(input, |o| o.fold_constants = true);
+        assert_eq!(fold_constants("x = 0 * -1"), "x=-0");
+        assert_eq!(fold_constants("x = -0 * 1"), "x=-0");
+        assert_eq!(fold_constants("x = 0 / -1"), "x=-0");
+        assert_eq!(fold_constants("x = 1 / (0 * -1)"), "x=-1/0");
+        assert_eq!(fold_constants("x = a - 0 * -1"), "x=a- -0");
+        assert_eq!(fold_constants("x = 0 * 1"), "x=0");
+        assert_eq!(fold_constants("x = '' + 0 * -1"), "x=\"0\"");
+    }
+}
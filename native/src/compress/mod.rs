@@ -0,0 +1,66 @@
+mod arrows;
+mod braces;
+mod branches;
+mod comparisons;
+mod conditionals;
+mod define;
+mod directives;
+mod drop;
+mod evaluate;
+mod fold;
+mod objects;
+mod parens;
+mod punctuation;
+mod unreachable;
+mod vars;
+
+use crate::buffer::TokenBuffer;
+use crate::options::MinifyOptions;
+
+/// Runs the transforms enabled in `options` over the token stream, before
+/// it is written out.
+pub fn compress(tokens: &mut TokenBuffer, options: &MinifyOptions) {
+    if !options.define.is_empty() {
+        define::substitute(tokens, &options.define);
+    }
+    if options.drop_debugger {
+        drop::drop_debugger(tokens);
+    }
+    if !options.pure_funcs.is_empty() {
+        drop::drop_pure_funcs(tokens, &options.pure_funcs);
+    }
+    if options.fold_constants {
+        fold::fold_constants(tokens);
+    }
+    if options.dead_code {
+        branches::remove_dead_branches(tokens);
+        unreachable::drop_unreachable(tokens);
+    }
+    if options.directives {
+        directives::dedupe_directives(tokens, options.module);
+    }
+    if options.conditionals {
+        conditionals::ifs_to_expressions(tokens);
+    }
+    if options.join_vars {
+        vars::join_vars(tokens);
+    }
+    if options.drop_braces {
+        braces::drop_braces(tokens);
+    }
+    if options.redundant_punctuation {
+        punctuation::drop_redundant_punctuation(tokens);
+    }
+    if options.arrows {
+        arrows::compact_arrows(tokens);
+    }
+    if options.comparisons {
+        comparisons::shorten_comparisons(tokens);
+    }
+    if options.shorthand && options.ecma() >= 2015 {
+        objects::shorten_properties(tokens);
+    }
+    if options.drop_parens {
+        parens::drop_parens(tokens);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, StringType, Token};
+use crate::util;
+
+/// Uses the ES2015 shorthands in object literals, like `{a:a}` into `{a}`
+/// and `{foo:function(){}}` into `{foo(){}}`.
+pub fn shorten_properties(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != Token::LeftBracket || !util::is_object_literal(tokens, i) {
+            i += 1;
+            continue;
+        }
+        let close = match util::matching_close(tokens, i) {
+            Some(close) => close,
+            None => break,
+        };
+        // going backwards, the properties before each one keep their place
+        for range in util::split_top_level(&tokens[i + 1..close])
+            .into_iter()
+            .rev()
+        {
+            let start = i + 1 + range.start;
+            let end = i + 1 + range.end;
+            if let Some(replacement) = shorthand(&tokens[start..end]) {
+                tokens.splice(start..end, replacement);
+            }
+        }
+        i += 1;
+    }
+}
+
+/// The shorter form of the property in `property`, if it has one.
+fn shorthand(property: &[Token]) -> Option<Vec<Token>> {
+    let colon = property
+        .iter()
+        .position(|tok| *tok == Token::Operator(Operator::Colon))?;
+    let key = &property[..colon];
+    let value = &property[colon + 1..];
+    // `{__proto__:x}` sets the prototype, `{__proto__}` and methods don't
+    let valid_key = match key {
+        [Token::Ident(name)] => name != "__proto__",
+        [Token::STRING(string)] => string.t != StringType::Backtick && string.value != "__proto__",
+        [Token::Keyword(_)] | [Token::NUMBER(_)] => true,
+        [Token::LeftBrace, .., Token::RightBrace] => {
+            util::matching_close(key, 0) == Some(key.len() - 1)
+        }
+        _ => false,
+    };
+    if !valid_key {
+        return None;
+    }
+    if let ([Token::Ident(name)], [Token::Ident(value)]) = (key, value) {
+        return if name == value {
+            Some(key.to_vec())
+        } else {
+            None
+        };
+    }
+    method(key, value)
+}
+
+/// Turns a property whose value is an anonymous function expression into a
+/// method, like `foo:function(a){}` into `foo(a){}`.
+fn method(key: &[Token], value: &[Token]) -> Option<Vec<Token>> {
+    let mut i = 0;
+    let is_async = value.first() == Some(&Token::Keyword(Keyword::ASYNC));
+    if is_async {
+        i += 1;
+    }
+    if value.get(i) != Some(&Token::Keyword(Keyword::FUNCTION)) {
+        return None;
+    }
+    i += 1;
+    let is_generator = value.get(i) == Some(&Token::Operator(Operator::Asterisk));
+    if is_generator {
+        i += 1;
+    }
+    // a named function expression can refer to itself by its name
+    if value.get(i) != Some(&Token::LeftParen) {
+        return None;
+    }
+    let params = i;
+    let body = util::matching_close(value, params)? + 1;
+    if value.get(body) != Some(&Token::LeftBracket)
+        || util::matching_close(value, body) != Some(value.len() - 1)
+    {
+        return None;
+    }
+    if uses_function_meta(&value[params..]) {
+        return None;
+    }
+    let mut method = Vec::new();
+    if is_async {
+        method.push(Token::Keyword(Keyword::ASYNC));
+    }
+    if is_generator {
+        method.push(Token::Operator(Operator::Asterisk));
+    }
+    method.extend_from_slice(key);
+    method.extend_from_slice(&value[params..]);
+    Some(method)
+}
+
+/// Whether a function refers to `arguments` or `new.target`, which are
+/// left alone rather than reasoned about.
+fn uses_function_meta(function: &[Token]) -> bool {
+    function.iter().enumerate().any(|(i, tok)| match tok {
+        Token::Ident(name) => name == "arguments",
+        Token::Keyword(Keyword::NEW) => {
+            function.get(i + 1) == Some(&Token::Operator(Operator::Period))
+                && function.get(i + 2) == Some(&Token::Ident("target".to_string()))
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn shortens_properties_and_methods() {
+        let shorten_properties = |input| {
+            minify_with(input, |o| {
+                o.shorthand = true;
+                o.ecma = Some(2015);
+            })
+        };
+        assert_eq!(shorten_properties("x = { a: a, b: c }"), "x={a,b:c}");
+        assert_eq!(
+            shorten_properties("x = { a: function () { return 1 } }"),
+            "x={a(){return 1}}"
+        );
+        assert_eq!(
+            shorten_properties("x = { a: function* () {}, b: async function () {} }"),
+            "x={*a(){},async b(){}}"
+        );
+        assert_eq!(
+            shorten_properties("x = { [\"a\"]: function () {} }"),
+            "x={[\"a\"](){}}"
+        );
+    }
+
+    #[test]
+    fn keeps_functions_that_refer_to_themselves() {
+        let shorten_properties = |input| {
+            minify_with(input, |o| {
+                o.shorthand = true;
+                o.ecma = Some(2015);
+            })
+        };
+        assert_eq!(
+            shorten_properties("x = { f: function f() { return f } }"),
+            "x={f:function f(){return f}}"
+        );
+        assert_eq!(
+            shorten_properties("x = { a: function () { return arguments } }"),
+            "x={a:function(){return arguments}}"
+        );
+    }
+
+    #[test]
+    fn keeps_properties_before_es2015() {
+        let shorthand = |input| minify_with(input, |o| o.shorthand = true);
+        assert_eq!(shorthand("x = { a: a }"), "x={a:a}");
+    }
+}
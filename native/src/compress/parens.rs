@@ -0,0 +1,282 @@
+use super::arrows;
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, StringType, Token};
+use crate::util;
+
+/// Removes parentheses that don't change how an expression is evaluated,
+/// like the ones in `return (a + b)` or `(a * b) + c`, and the ones around a
+/// single arrow function parameter.
+pub fn drop_parens(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != Token::LeftParen {
+            i += 1;
+            continue;
+        }
+        let close = match util::matching_close(tokens, i) {
+            Some(close) => close,
+            None => break,
+        };
+        let removable = if tokens.get(close + 1) == Some(&Token::Operator(Operator::Arrow)) {
+            arrows::is_single_param(tokens, i)
+        } else {
+            close > i + 1 && is_grouping(tokens, i) && is_redundant(tokens, i, close)
+        };
+        if removable {
+            let inner = tokens[i + 1..close].to_vec();
+            tokens.splice(i..close + 1, inner);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Whether the `(` at `open` groups an expression, rather than starting the
+/// arguments of a call or the head of a statement or function.
+fn is_grouping(tokens: &[Token], open: usize) -> bool {
+    if open == 0 {
+        return true;
+    }
+    match &tokens[open - 1] {
+        Token::RightParen => util::closes_header(tokens, open - 1),
+        Token::Keyword(keyword) => matches!(
+            keyword,
+            Keyword::RETURN
+                | Keyword::THROW
+                | Keyword::CASE
+                | Keyword::TYPEOF
+                | Keyword::VOID
+                | Keyword::DELETE
+                | Keyword::AWAIT
+                | Keyword::NEW
+                | Keyword::YIELD
+                | Keyword::IN
+                | Keyword::INSTANCEOF
+                | Keyword::ELSE
+                | Keyword::DO
+                | Keyword::EXTENDS
+        ),
+        tok => !tok.ends_expression(),
+    }
+}
+
+/// Whether the parentheses from `open` to `close` can go without changing
+/// how the code parses.
+fn is_redundant(tokens: &[Token], open: usize, close: usize) -> bool {
+    let inner = &tokens[open + 1..close];
+    let first = &inner[0];
+    if *first == Token::Keyword(Keyword::LET) {
+        return false;
+    }
+    let statement = util::is_statement_start(tokens, open);
+    // `{}`, `function` and `class` would start a declaration, and a string
+    // would become a directive
+    if statement && util::needs_statement_parens(inner) {
+        return false;
+    }
+    let after = tokens.get(close + 1);
+    let prec = util::expression_precedence(inner);
+    let left = if statement {
+        precedence::COMMA
+    } else {
+        match left_precedence(tokens, open) {
+            Some(left) => left,
+            None => return false,
+        }
+    };
+    let right = match right_precedence(tokens, close) {
+        Some(right) => right,
+        None => return false,
+    };
+    if prec < left.max(right) {
+        return false;
+    }
+    match open.checked_sub(1).map(|k| &tokens[k]) {
+        // `new (a())` calls `a`, `new a()` doesn't
+        Some(Token::Keyword(Keyword::NEW)) if !is_member_chain(inner) => return false,
+        // `=> {}` would be a function body
+        Some(Token::Operator(Operator::Arrow)) if *first == Token::LeftBracket => return false,
+        _ => {}
+    }
+    let called = match after {
+        Some(Token::LeftParen) => true,
+        Some(Token::STRING(string)) => string.t == StringType::Backtick,
+        _ => false,
+    };
+    if called && util::needs_statement_parens(inner) {
+        return false;
+    }
+    // `1.toString()` would read `1.` as the number
+    if after == Some(&Token::Operator(Operator::Period)) && matches!(first, Token::NUMBER(_)) {
+        return false;
+    }
+    if mixes_nullish(tokens, open, close) {
+        return false;
+    }
+    // `in` in the head of a `for` would make it a `for`-`in` loop
+    !(util::has_top_level(inner, |tok| *tok == Token::Keyword(Keyword::IN))
+        && in_for_header(tokens, open))
+}
+
+/// The lowest precedence an expression can have after the token before
+/// `open`, or `None` if that token isn't understood.
+fn left_precedence(tokens: &[Token], open: usize) -> Option<u8> {
+    let tok = &tokens[open - 1];
+    let prec = match tok {
+        Token::LeftParen => {
+            let header = open > 1
+                && matches!(
+                    tokens[open - 2],
+                    Token::Keyword(Keyword::IF)
+                        | Token::Keyword(Keyword::WHILE)
+                        | Token::Keyword(Keyword::SWITCH)
+                        | Token::Keyword(Keyword::WITH)
+                        | Token::Keyword(Keyword::FOR)
+                );
+            if header || is_grouping(tokens, open - 1) {
+                precedence::COMMA
+            } else {
+                precedence::ASSIGNMENT
+            }
+        }
+        // a computed member rather than an array element
+        Token::LeftBrace if open > 1 && tokens[open - 2].ends_expression() => precedence::COMMA,
+        Token::Keyword(Keyword::RETURN)
+        | Token::Keyword(Keyword::THROW)
+        | Token::Keyword(Keyword::CASE) => precedence::COMMA,
+        Token::LeftBrace
+        | Token::Comma
+        | Token::Keyword(Keyword::YIELD)
+        | Token::Operator(Operator::Spread)
+        | Token::Operator(Operator::Arrow)
+        | Token::Operator(Operator::Ternary)
+        | Token::Operator(Operator::Colon) => precedence::ASSIGNMENT,
+        Token::Keyword(Keyword::NEW) | Token::Keyword(Keyword::EXTENDS) => precedence::MEMBER,
+        Token::Operator(op) if op.is_assignment() => precedence::ASSIGNMENT,
+        // `**` groups to the right
+        Token::Operator(Operator::Exponent) => precedence::EXPONENT,
+        tok if util::is_binary_at(tokens, open - 1) => tok.binary_precedence()? + 1,
+        Token::Operator(Operator::Bang)
+        | Token::Operator(Operator::BitwiseNot)
+        | Token::Operator(Operator::Plus)
+        | Token::Operator(Operator::Minus)
+        | Token::Keyword(Keyword::TYPEOF)
+        | Token::Keyword(Keyword::VOID)
+        | Token::Keyword(Keyword::DELETE)
+        | Token::Keyword(Keyword::AWAIT) => precedence::UNARY,
+        Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus) => {
+            precedence::MEMBER
+        }
+        _ => return None,
+    };
+    Some(prec)
+}
+
+/// The lowest precedence an expression can have before the token after
+/// `close`, or `None` if that token isn't understood.
+fn right_precedence(tokens: &[Token], close: usize) -> Option<u8> {
+    let tok = match tokens.get(close + 1) {
+        Some(tok) => tok,
+        None => return Some(0),
+    };
+    let prec = match tok {
+        Token::RightParen
+        | Token::RightBrace
+        | Token::RightBracket
+        | Token::Semicolon
+        | Token::Comma
+        | Token::Operator(Operator::Colon) => 0,
+        // the body of a class after `extends`
+        Token::LeftBracket => 0,
+        Token::Operator(Operator::Ternary) => precedence::LOGICAL_OR,
+        Token::Operator(op) if op.is_assignment() => precedence::MEMBER,
+        // `**` can't follow a unary operator
+        Token::Operator(Operator::Exponent) => precedence::POSTFIX,
+        tok if util::is_binary_at(tokens, close + 1) => tok.binary_precedence()?,
+        Token::LeftParen
+        | Token::LeftBrace
+        | Token::Operator(Operator::Period)
+        | Token::Operator(Operator::PlusPlus)
+        | Token::Operator(Operator::MinusMinus) => precedence::MEMBER,
+        Token::STRING(string) if string.t == StringType::Backtick => precedence::MEMBER,
+        _ => return None,
+    };
+    Some(prec)
+}
+
+/// Whether `tokens` is a name or a chain of plain property reads, which
+/// `new` can take without parentheses.
+fn is_member_chain(tokens: &[Token]) -> bool {
+    tokens.iter().enumerate().all(|(i, tok)| match tok {
+        Token::Ident(_) => true,
+        Token::Keyword(Keyword::THIS) => i == 0,
+        Token::Operator(Operator::Period) => i % 2 == 1,
+        _ => false,
+    })
+}
+
+/// Whether removing the parentheses would put `??` next to `||` or `&&`
+/// without parentheses between them, which is a syntax error.
+fn mixes_nullish(tokens: &[Token], open: usize, close: usize) -> bool {
+    let is_nullish = |tok: &Token| *tok == Token::Operator(Operator::NullishCoalescing);
+    let is_logical = |tok: &Token| {
+        matches!(
+            tok,
+            Token::Operator(Operator::LogicalOr) | Token::Operator(Operator::LogicalAnd)
+        )
+    };
+    let neighbours = [open.checked_sub(1), Some(close + 1)];
+    neighbours
+        .iter()
+        .filter_map(|&i| tokens.get(i?))
+        .any(|tok| {
+            (is_nullish(tok) && util::has_top_level(&tokens[open + 1..close], is_logical))
+                || (is_logical(tok) && util::has_top_level(&tokens[open + 1..close], is_nullish))
+        })
+}
+
+/// Whether the brackets `i` is directly inside of are the head of a `for`
+/// loop.
+fn in_for_header(tokens: &[Token], i: usize) -> bool {
+    let mut depth = 0;
+    for k in (0..i).rev() {
+        match &tokens[k] {
+            Token::RightParen | Token::RightBrace | Token::RightBracket => depth += 1,
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket if depth > 0 => depth -= 1,
+            Token::LeftParen => {
+                let keyword = match k.checked_sub(1).map(|k| &tokens[k]) {
+                    Some(Token::Keyword(Keyword::AWAIT)) if k > 1 => Some(&tokens[k - 2]),
+                    keyword => keyword,
+                };
+                return keyword == Some(&Token::Keyword(Keyword::FOR));
+            }
+            Token::LeftBrace | Token::LeftBracket => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_redundant_parens() {
+        let drop_parens = |input| minify_with(input, |o| o.drop_parens = true);
+        assert_eq!(drop_parens("x = (a * b) + c"), "x=a*b+c");
+        assert_eq!(drop_parens("x = ((a))"), "x=a");
+        assert_eq!(drop_parens("(a)()"), "a()");
+        assert_eq!(drop_parens("return (a)"), "return a");
+    }
+
+    #[test]
+    fn keeps_parens_that_group() {
+        let drop_parens = |input| minify_with(input, |o| o.drop_parens = true);
+        assert_eq!(drop_parens("x = (a + b) * c"), "x=(a+b)*c");
+        assert_eq!(drop_parens("x = a - (b - c)"), "x=a-(b-c)");
+        assert_eq!(drop_parens("x = (a, b)"), "x=(a,b)");
+        assert_eq!(drop_parens("x = (a ? b : c) ? d : e"), "x=(a?b:c)?d:e");
+        assert_eq!(drop_parens("(function () {})()"), "(function(){})()");
+    }
+}
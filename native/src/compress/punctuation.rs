@@ -0,0 +1,147 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, Token};
+use crate::util;
+
+/// Removes empty statements like `;;`, empty `else{}` branches, `if`
+/// statements with an empty body and a condition without side effects, and
+/// trailing commas like in `[1,2,]`.
+pub fn drop_redundant_punctuation(tokens: &mut TokenBuffer) {
+    // going backwards, an `if` is looked at after its `else` is gone
+    let mut i = tokens.len();
+    while i > 0 {
+        i -= 1;
+        let end = match &tokens[i] {
+            Token::Comma if is_trailing_comma(tokens, i) => i + 1,
+            Token::Semicolon if is_empty_statement(tokens, i) => i + 1,
+            Token::LeftBracket if is_empty_block(tokens, i) => i + 2,
+            Token::Keyword(Keyword::ELSE) => match empty_else(tokens, i) {
+                Some(end) => end,
+                None => continue,
+            },
+            Token::Keyword(Keyword::IF) => match empty_if(tokens, i) {
+                Some(end) => end,
+                None => continue,
+            },
+            _ => continue,
+        };
+        // the body of another statement can't be left empty
+        if util::is_body_position(tokens, i) {
+            tokens.splice(i..end, vec![Token::Semicolon]);
+        } else {
+            tokens.drain(i..end);
+        }
+    }
+}
+
+/// Whether the `,` at `i` ends a list without anything after it. In an
+/// array, a comma after a hole is part of the array's length.
+fn is_trailing_comma(tokens: &[Token], i: usize) -> bool {
+    match tokens.get(i + 1) {
+        Some(Token::RightParen) | Some(Token::RightBracket) => true,
+        Some(Token::RightBrace) => !matches!(tokens[i - 1], Token::Comma | Token::LeftBrace),
+        _ => false,
+    }
+}
+
+/// Whether the `;` at `i` is a statement of its own, rather than the end of
+/// one or a part of a `for` loop's head.
+fn is_empty_statement(tokens: &[Token], i: usize) -> bool {
+    if util::is_body_position(tokens, i) {
+        return false;
+    }
+    match i.checked_sub(1).map(|k| &tokens[k]) {
+        None | Some(Token::LeftBracket) => true,
+        Some(Token::Semicolon) => !in_parens(tokens, i),
+        // only after a block, `x={};` needs its semicolon
+        Some(Token::RightBracket) => match util::matching_open(tokens, i - 1) {
+            Some(open) => util::is_statement_start(tokens, open),
+            None => false,
+        },
+        Some(Token::Operator(Operator::Colon)) => util::is_statement_start(tokens, i),
+        _ => false,
+    }
+}
+
+fn in_parens(tokens: &[Token], i: usize) -> bool {
+    let mut depth = 0;
+    for tok in tokens[..i].iter().rev() {
+        match tok {
+            Token::RightParen | Token::RightBrace | Token::RightBracket => depth += 1,
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket if depth > 0 => depth -= 1,
+            Token::LeftParen => return true,
+            Token::LeftBrace | Token::LeftBracket => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whether the `{` at `i` starts an empty block statement.
+fn is_empty_block(tokens: &[Token], i: usize) -> bool {
+    tokens.get(i + 1) == Some(&Token::RightBracket)
+        && util::is_statement_start(tokens, i)
+        && !util::is_body_position(tokens, i)
+}
+
+/// Where an `else{}` or `else;` starting at `i` ends.
+fn empty_else(tokens: &[Token], i: usize) -> Option<usize> {
+    let end = util::statement_end(tokens, i + 1);
+    if !is_empty_body(&tokens[i + 1..end]) {
+        return None;
+    }
+    // `if(a)if(b)c();else{}else d()` would give the last `else` to the
+    // inner `if`
+    if tokens.get(end) == Some(&Token::Keyword(Keyword::ELSE)) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Where an `if` starting at `i` ends, when it has an empty body, no `else`
+/// and a condition without side effects.
+fn empty_if(tokens: &[Token], i: usize) -> Option<usize> {
+    if tokens.get(i + 1) != Some(&Token::LeftParen) {
+        return None;
+    }
+    let close = util::matching_close(tokens, i + 1)?;
+    let end = util::statement_end(tokens, close + 1);
+    if !is_empty_body(&tokens[close + 1..end])
+        || tokens.get(end) == Some(&Token::Keyword(Keyword::ELSE))
+        || !util::is_side_effect_free(&tokens[i + 2..close])
+    {
+        return None;
+    }
+    Some(end)
+}
+
+fn is_empty_body(body: &[Token]) -> bool {
+    body == [Token::Semicolon] || body == [Token::LeftBracket, Token::RightBracket]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_empty_statements_and_trailing_commas() {
+        let drop_redundant_punctuation =
+            |input| minify_with(input, |o| o.redundant_punctuation = true);
+        assert_eq!(drop_redundant_punctuation("a();;; b();"), "a();b()");
+        assert_eq!(
+            drop_redundant_punctuation("var a = [1, 2, 3,]"),
+            "var a=[1,2,3]"
+        );
+        assert_eq!(drop_redundant_punctuation("f(a, b,)"), "f(a,b)");
+    }
+
+    #[test]
+    fn keeps_empty_bodies() {
+        let drop_redundant_punctuation =
+            |input| minify_with(input, |o| o.redundant_punctuation = true);
+        assert_eq!(drop_redundant_punctuation("for (;;) ;"), "for(;;);");
+        assert_eq!(
+            drop_redundant_punctuation("if (a) ; else b()"),
+            "if(a);else b()"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{Keyword, Operator, Token};
+use crate::util;
+
+/// Removes the statements after a `return`, `throw`, `break` or `continue`
+/// in the same block, up to the next `case` or `default` label. Function
+/// declarations are kept, and `var` declarations are left behind without
+/// their values, since both are hoisted.
+pub fn drop_unreachable(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let completes = matches!(
+            tokens[i],
+            Token::Keyword(Keyword::RETURN)
+                | Token::Keyword(Keyword::THROW)
+                | Token::Keyword(Keyword::BREAK)
+                | Token::Keyword(Keyword::CONTINUE)
+        );
+        if !completes || !util::is_statement_start(tokens, i) || util::is_body_position(tokens, i) {
+            i += 1;
+            continue;
+        }
+        let start = util::statement_end(tokens, i);
+        let mut end = start;
+        let mut names: Vec<String> = Vec::new();
+        let mut functions = Vec::new();
+        let mut lexical = false;
+        while end < tokens.len() {
+            let stops = match &tokens[end] {
+                Token::RightBracket
+                | Token::Keyword(Keyword::CASE)
+                | Token::Keyword(Keyword::IMPORT)
+                | Token::Keyword(Keyword::EXPORT) => true,
+                Token::Keyword(Keyword::DEFAULT) => {
+                    tokens.get(end + 1) == Some(&Token::Operator(Operator::Colon))
+                }
+                _ => false,
+            };
+            let statement_end = util::statement_end(tokens, end);
+            if stops || statement_end == end {
+                break;
+            }
+            let statement = &tokens[end..statement_end];
+            match &statement[0] {
+                // a `let` that is never reached still shadows outer bindings
+                Token::Keyword(Keyword::LET)
+                | Token::Keyword(Keyword::CONST)
+                | Token::Keyword(Keyword::CLASS) => lexical = true,
+                Token::Keyword(Keyword::FUNCTION) => functions.extend_from_slice(statement),
+                Token::Keyword(Keyword::ASYNC)
+                    if statement.get(1) == Some(&Token::Keyword(Keyword::FUNCTION)) =>
+                {
+                    functions.extend_from_slice(statement)
+                }
+                _ => {
+                    for name in util::var_declarations(statement) {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                }
+            }
+            end = statement_end;
+        }
+        if lexical || start == end {
+            i = end.max(i + 1);
+            continue;
+        }
+        let mut replacement = Vec::new();
+        if !names.is_empty() {
+            replacement.push(Token::Keyword(Keyword::VAR));
+            for (n, name) in names.into_iter().enumerate() {
+                if n > 0 {
+                    replacement.push(Token::Comma);
+                }
+                replacement.push(Token::Ident(name));
+            }
+            replacement.push(Token::Semicolon);
+        }
+        replacement.extend(functions);
+        let len = replacement.len();
+        tokens.splice(start..end, replacement);
+        i = start + len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn drops_statements_after_completions() {
+        let drop_unreachable = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(
+            drop_unreachable("function f() { throw e; x() }"),
+            "function f(){throw e}"
+        );
+        assert_eq!(
+            drop_unreachable("for (;;) { break; a() }"),
+            "for(;;){break}"
+        );
+        assert_eq!(
+            drop_unreachable("switch (x) { case 1: a(); break; b(); case 2: c() }"),
+            "switch(x){case 1:a();break;case 2:c()}"
+        );
+    }
+
+    #[test]
+    fn keeps_hoisted_declarations() {
+        let drop_unreachable = |input| minify_with(input, |o| o.dead_code = true);
+        assert_eq!(
+            drop_unreachable("function f() { return 1; a(); var b = 2; function g() {} }"),
+            "function f(){return 1;var b;function g(){}}"
+        );
+    }
+}
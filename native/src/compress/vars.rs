@@ -0,0 +1,147 @@
+use crate::buffer::TokenBuffer;
+use crate::token::{precedence, Keyword, Operator, StringType, Token};
+use crate::util;
+
+/// Joins consecutive `var`, `let` or `const` declarations of the same kind
+/// into one, like `var a=1;var b=2` into `var a=1,b=2`, and moves an
+/// assignment right after a declaration into it, like `let x;x=5` into
+/// `let x=5`.
+pub fn join_vars(tokens: &mut TokenBuffer) {
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_declaration = matches!(
+            tokens[i],
+            Token::Keyword(Keyword::VAR)
+                | Token::Keyword(Keyword::LET)
+                | Token::Keyword(Keyword::CONST)
+        );
+        // a `var` that is the body of an `if` only runs its initializers
+        // when the condition holds
+        if !is_declaration
+            || !util::is_statement_start(tokens, i)
+            || util::is_body_position(tokens, i)
+        {
+            i += 1;
+            continue;
+        }
+        let end = util::declaration_end(tokens, i + 1);
+        if tokens.get(end) != Some(&Token::Semicolon) {
+            i = end;
+            continue;
+        }
+        if tokens.get(end + 1) == Some(&tokens[i]) {
+            tokens.splice(end..end + 2, vec![Token::Comma]);
+        } else if joins_assignment(tokens, i, end) {
+            tokens.drain(end..end + 2);
+        } else {
+            i = end;
+        }
+    }
+}
+
+/// Whether the statement after the declaration from `start` to the
+/// semicolon at `end` assigns to its last declarator, which has no
+/// initializer, so that the value can be its initializer instead.
+fn joins_assignment(tokens: &[Token], start: usize, end: usize) -> bool {
+    if tokens[start] == Token::Keyword(Keyword::CONST) {
+        return false;
+    }
+    let name = match &tokens[end - 1] {
+        Token::Ident(name) if end - 2 == start || tokens[end - 2] == Token::Comma => name,
+        _ => return false,
+    };
+    if tokens.get(end + 1) != Some(&tokens[end - 1])
+        || tokens.get(end + 2) != Some(&Token::Operator(Operator::Assign))
+    {
+        return false;
+    }
+    let value_end = util::operand_end(tokens, end + 3, precedence::ASSIGNMENT);
+    if value_end == end + 3
+        || !matches!(
+            tokens.get(value_end),
+            None | Some(Token::Semicolon) | Some(Token::RightBracket)
+        )
+    {
+        return false;
+    }
+    // a `let` can't be read before its initializer is done, so the value
+    // can't use it, or run code that might
+    let value = &tokens[end + 3..value_end];
+    tokens[start] == Token::Keyword(Keyword::VAR)
+        || (!value.iter().any(|tok| *tok == Token::Ident(name.clone())) && !may_run_code(value))
+}
+
+/// Whether evaluating `expr` may call code from elsewhere, through a call,
+/// `new`, a tagged template, a getter or an operator that calls `valueOf`.
+fn may_run_code(expr: &[Token]) -> bool {
+    expr.iter().enumerate().any(|(i, tok)| {
+        let after_expression = i > 0 && expr[i - 1].ends_expression();
+        match tok {
+            Token::LeftParen | Token::LeftBrace => after_expression,
+            Token::STRING(string) => {
+                string.t == StringType::Backtick
+                    && (after_expression || string.value.contains("${"))
+            }
+            Token::Keyword(keyword) => !matches!(
+                keyword,
+                Keyword::THIS
+                    | Keyword::TRUE
+                    | Keyword::FALSE
+                    | Keyword::NULL
+                    | Keyword::TYPEOF
+                    | Keyword::VOID
+            ),
+            Token::Operator(op) => !matches!(
+                op,
+                Operator::Bang
+                    | Operator::Ternary
+                    | Operator::Colon
+                    | Operator::LogicalAnd
+                    | Operator::LogicalOr
+                    | Operator::NullishCoalescing
+                    | Operator::StrictEqual
+                    | Operator::StrictNotEqual
+            ),
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minify::minify_with;
+
+    #[test]
+    fn joins_declarations() {
+        let join_vars = |input| minify_with(input, |o| o.join_vars = true);
+        assert_eq!(join_vars("var a = 1; var b = 2; var c"), "var a=1,b=2,c");
+        assert_eq!(join_vars("let a = 1; const b = 2"), "let a=1;const b=2");
+        assert_eq!(
+            join_vars("if (x) var a = 1; var b = 2"),
+            "if(x)var a=1;var b=2"
+        );
+    }
+
+    #[test]
+    fn joins_assignments() {
+        let join_vars = |input| minify_with(input, |o| o.join_vars = true);
+        assert_eq!(join_vars("var x; x = f()"), "var x=f()");
+        assert_eq!(join_vars("let x; x = [1, 'a', y]"), "let x=[1,'a',y]");
+        assert_eq!(join_vars("let x; x = a ? b : c"), "let x=a?b:c");
+    }
+
+    #[test]
+    fn keeps_let_assignments_that_may_read_it() {
+        let join_vars = |input| minify_with(input, |o| o.join_vars = true);
+        assert_eq!(
+            join_vars("let x; x = f(); function f() { return x }"),
+            "let x;x=f();function f(){return x}"
+        );
+        assert_eq!(join_vars("let x; x = new A()"), "let x;x=new A()");
+        assert_eq!(join_vars("let x; x = a.b"), "let x;x=a.b");
+        assert_eq!(join_vars("let x; x = a[0]"), "let x;x=a[0]");
+        assert_eq!(join_vars("let x; x = tag`a`"), "let x;x=tag`a`");
+        assert_eq!(join_vars("let x; x = a + 1"), "let x;x=a+1");
+        assert_eq!(join_vars("let x; x = x || 1"), "let x;x=x||1");
+    }
+}
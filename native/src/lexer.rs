@@ -1,28 +1,269 @@
 use std::iter::Peekable;
 use std::str::Chars;
 
-use super::token::{lookup_keyword, CommentType, Operator, StringObject, Token};
+use super::buffer::Position;
+use super::token::{
+    lookup_keyword, Comment, CommentType, Keyword, Operator, StringObject, StringType, Token,
+};
+
+/// What an open paren belongs to, so its closing paren can be told apart from
+/// the end of an expression.
+#[derive(Clone, Copy, PartialEq)]
+enum Paren {
+    Header,
+    DoWhile,
+    Group,
+}
 
 pub struct Lexer<'a> {
+    source: &'a str,
     input: Peekable<Chars<'a>>,
+    position: Position,
+    start: Option<Position>,
+    comments: Vec<Comment>,
+
+    // State for automatic semicolon insertion. Line breaks are dropped along
+    // with the rest of the whitespace, so the semicolons they stand for are
+    // emitted as tokens instead.
+    last: Option<Token>,
+    last_ends_expression: bool,
+    last_starts_statement: bool,
+    newline: bool,
+    pending: Option<(Token, Option<Position>, Vec<Comment>)>,
+    parens: Vec<Paren>,
+    next_paren: Option<Paren>,
+    braces: Vec<bool>,
+    expression_body: Option<usize>,
+    dos: Vec<usize>,
+    // Depths of the `for` heads the lexer is directly in, where `of` goes on
+    // with the head, and whether it is in an import or export clause, where
+    // `from` and `as` do.
+    for_heads: Vec<usize>,
+    next_for: bool,
+    module_clause: bool,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &str) -> Lexer {
+    pub fn new(input: &str) -> Lexer<'_> {
         Lexer {
+            source: input,
             input: input.chars().peekable(),
+            position: Position { line: 0, column: 0 },
+            start: None,
+            comments: Vec::new(),
+            last: None,
+            last_ends_expression: false,
+            last_starts_statement: true,
+            newline: false,
+            pending: None,
+            parens: Vec::new(),
+            next_paren: None,
+            braces: Vec::new(),
+            expression_body: None,
+            dos: Vec::new(),
+            for_heads: Vec::new(),
+            next_for: false,
+            module_clause: false,
         }
     }
 
     pub fn read(&mut self) -> Option<char> {
-        self.input.next()
+        let ch = self.input.next()?;
+        let crlf = ch == '\r' && self.peek() == Some(&'\n');
+        if is_line_terminator(ch) && !crlf {
+            self.position.line += 1;
+            self.position.column = 0;
+        } else {
+            self.position.column += ch.len_utf16() as u32;
+        }
+        Some(ch)
     }
     pub fn peek(&mut self) -> Option<&char> {
         self.input.peek()
     }
 
     pub fn next_token(&mut self) -> Token {
+        let tok = match self.pending.take() {
+            Some((tok, start, comments)) => {
+                self.start = start;
+                self.comments = comments;
+                tok
+            }
+            None => {
+                self.newline = false;
+                let tok = self.read_token();
+                if self.inserts_semicolon(&tok) {
+                    let comments = std::mem::take(&mut self.comments);
+                    self.pending = Some((tok, self.start.take(), comments));
+                    Token::Semicolon
+                } else {
+                    tok
+                }
+            }
+        };
+        self.track(&tok);
+        tok
+    }
+
+    /// The input the lexer reads from.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Where the token last returned by `next_token` starts, or `None` if it
+    /// is a semicolon that was inserted at a line break.
+    pub fn token_start(&self) -> Option<Position> {
+        self.start
+    }
+
+    /// Takes the comments that came before the token last returned by
+    /// `next_token`.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
+    fn depth(&self) -> usize {
+        self.parens.len() + self.braces.len()
+    }
+
+    /// Whether a line break between the last token and `next` ends the
+    /// statement, following the automatic semicolon insertion rules.
+    fn inserts_semicolon(&self, next: &Token) -> bool {
+        if !self.newline {
+            return false;
+        }
+        if let Some(Token::Keyword(
+            Keyword::RETURN
+            | Keyword::BREAK
+            | Keyword::CONTINUE
+            | Keyword::YIELD
+            | Keyword::DEBUGGER,
+        )) = &self.last
+        {
+            return !matches!(next, Token::Semicolon | Token::RightBracket | Token::EOF);
+        }
+        if !self.last_ends_expression {
+            return false;
+        }
+        match next {
+            // contextual keywords that go on with the statement
+            Token::Ident(value) => match value.as_str() {
+                "of" => self.for_heads.last() != Some(&self.depth()),
+                "from" | "as" => !self.module_clause,
+                _ => true,
+            },
+            Token::NUMBER(_) => true,
+            Token::STRING(string) => string.t != StringType::Backtick,
+            Token::Keyword(keyword) => !matches!(
+                keyword,
+                Keyword::IN | Keyword::INSTANCEOF | Keyword::EXTENDS
+            ),
+            Token::Operator(op) => matches!(
+                op,
+                Operator::PlusPlus | Operator::MinusMinus | Operator::Bang | Operator::BitwiseNot
+            ),
+            _ => false,
+        }
+    }
+
+    fn track(&mut self, tok: &Token) {
+        let starts_statement = match &self.last {
+            None | Some(Token::Semicolon) | Some(Token::LeftBracket) => true,
+            Some(Token::RightBracket) => !self.last_ends_expression,
+            Some(Token::RightParen) => !self.last_ends_expression,
+            Some(Token::Keyword(Keyword::ELSE)) | Some(Token::Keyword(Keyword::DO)) => true,
+            _ => false,
+        };
+        let mut ends_expression = tok.ends_expression();
+        if self.last == Some(Token::Keyword(Keyword::EXPORT))
+            && !matches!(
+                tok,
+                Token::LeftBracket | Token::Operator(Operator::Asterisk)
+            )
+        {
+            self.module_clause = false;
+        }
+        match tok {
+            Token::Keyword(Keyword::IF)
+            | Token::Keyword(Keyword::FOR)
+            | Token::Keyword(Keyword::WITH)
+            | Token::Keyword(Keyword::SWITCH)
+            | Token::Keyword(Keyword::CATCH) => {
+                self.next_paren = Some(Paren::Header);
+                self.next_for = *tok == Token::Keyword(Keyword::FOR);
+            }
+            Token::Keyword(Keyword::IMPORT) => self.module_clause = starts_statement,
+            Token::Keyword(Keyword::EXPORT) => self.module_clause = true,
+            Token::STRING(_) | Token::Semicolon => self.module_clause = false,
+            Token::Keyword(Keyword::WHILE) => {
+                if self.dos.last() == Some(&self.depth()) {
+                    self.dos.pop();
+                    self.next_paren = Some(Paren::DoWhile);
+                } else {
+                    self.next_paren = Some(Paren::Header);
+                }
+            }
+            Token::Keyword(Keyword::DO) => self.dos.push(self.depth()),
+            Token::Keyword(Keyword::FUNCTION) | Token::Keyword(Keyword::CLASS) => {
+                let declaration = starts_statement
+                    || matches!(
+                        self.last,
+                        Some(Token::Keyword(Keyword::EXPORT))
+                            | Some(Token::Keyword(Keyword::DEFAULT))
+                    )
+                    || (self.last == Some(Token::Keyword(Keyword::ASYNC))
+                        && self.last_starts_statement);
+                if !declaration {
+                    self.expression_body = Some(self.depth());
+                }
+            }
+            Token::LeftParen => {
+                let paren = self.next_paren.take().unwrap_or(Paren::Group);
+                self.parens.push(paren);
+                if std::mem::take(&mut self.next_for) {
+                    self.for_heads.push(self.depth());
+                }
+            }
+            Token::RightParen => {
+                if self.for_heads.last() == Some(&self.depth()) {
+                    self.for_heads.pop();
+                }
+                ends_expression = self.parens.pop() != Some(Paren::Header);
+            }
+            Token::LeftBracket => {
+                let expression = if self.expression_body == Some(self.depth()) {
+                    self.expression_body = None;
+                    true
+                } else {
+                    !starts_statement
+                        && !matches!(
+                            self.last,
+                            Some(Token::RightParen)
+                                | Some(Token::Ident(_))
+                                | Some(Token::Keyword(Keyword::TRY))
+                                | Some(Token::Keyword(Keyword::FINALLY))
+                        )
+                };
+                self.braces.push(expression);
+            }
+            Token::RightBracket => {
+                ends_expression = self.braces.pop().unwrap_or(false);
+            }
+            // only a postfix `++` ends an expression, a prefix one still
+            // needs its operand
+            Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus) => {
+                ends_expression = self.last_ends_expression;
+            }
+            _ => {}
+        }
+        self.last = Some(tok.clone());
+        self.last_ends_expression = ends_expression;
+        self.last_starts_statement = starts_statement;
+    }
+
+    fn read_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.start = Some(self.position);
         match self.read() {
             Some(ch) => {
                 match ch {
@@ -33,21 +274,21 @@ impl<'a> Lexer<'a> {
                     '{' => Token::LeftBracket,
                     '}' => Token::RightBracket,
                     ',' => Token::Comma,
-                    ';' => self.next_token(),
+                    ';' => Token::Semicolon,
                     ':' => Token::Operator(Operator::Colon),
                     '~' => Token::Operator(Operator::BitwiseNot),
-                    '^' => Token::Operator(Operator::BitwiseXor),
+                    '^' => self.read_assign(Operator::BitwiseXor, Operator::BitwiseXorAssign),
                     '?' => match self.peek() {
                         Some('?') => {
                             self.read();
-                            Token::Operator(Operator::NullishCoalescing)
+                            self.read_assign(Operator::NullishCoalescing, Operator::NullishAssign)
                         }
                         _ => Token::Operator(Operator::Ternary),
                     },
                     '&' => match self.peek() {
                         Some('&') => {
                             self.read();
-                            Token::Operator(Operator::LogicalAnd)
+                            self.read_assign(Operator::LogicalAnd, Operator::LogicalAndAssign)
                         }
                         Some('=') => {
                             self.read();
@@ -58,7 +299,7 @@ impl<'a> Lexer<'a> {
                     '|' => match self.peek() {
                         Some('|') => {
                             self.read();
-                            Token::Operator(Operator::LogicalOr)
+                            self.read_assign(Operator::LogicalOr, Operator::LogicalOrAssign)
                         }
                         Some('=') => {
                             self.read();
@@ -82,7 +323,7 @@ impl<'a> Lexer<'a> {
                             self.read();
                             Token::Operator(Operator::MinusAssign)
                         }
-                        Some('+') => {
+                        Some('-') => {
                             self.read();
                             Token::Operator(Operator::MinusMinus)
                         }
@@ -105,17 +346,21 @@ impl<'a> Lexer<'a> {
                         }
                         _ => Token::Operator(Operator::Asterisk),
                     },
-                    '/' => match self.peek() {
+                    '/' => match self.peek().copied() {
                         Some('/') => {
-                            self.read_comment(CommentType::SingleLine);
-
-                            self.next_token()
+                            self.read();
+                            let comment = self.read_comment(CommentType::SingleLine);
+                            self.comments.push(comment);
+                            self.read_token()
                         }
                         Some('*') => {
                             self.read();
-                            self.read_comment(CommentType::MultiLine);
-                            self.next_token()
+                            let comment = self.read_comment(CommentType::MultiLine);
+                            self.comments.push(comment);
+                            self.read_token()
                         }
+                        // with no expression before it, a slash starts a regex
+                        _ if !self.last_ends_expression => Token::REGEX(self.read_regex()),
                         Some('=') => {
                             self.read();
                             Token::Operator(Operator::SlashAssign)
@@ -174,7 +419,7 @@ impl<'a> Lexer<'a> {
                             }
                             Some(&ch) => {
                                 if ch.is_numeric() {
-                                    let number = self.read_number(ch);
+                                    let number = self.read_number('.');
                                     return Token::NUMBER(number);
                                 }
                                 Token::Operator(Operator::Period)
@@ -188,9 +433,15 @@ impl<'a> Lexer<'a> {
                             match self.peek() {
                                 Some('>') => {
                                     self.read();
-                                    Token::Operator(Operator::UnsignedBitwiseRight)
+                                    self.read_assign(
+                                        Operator::UnsignedBitwiseRight,
+                                        Operator::UnsignedBitwiseRightAssign,
+                                    )
                                 }
-                                _ => Token::Operator(Operator::BitwiseRight),
+                                _ => self.read_assign(
+                                    Operator::BitwiseRight,
+                                    Operator::BitwiseRightAssign,
+                                ),
                             }
                         }
                         Some('=') => {
@@ -202,7 +453,7 @@ impl<'a> Lexer<'a> {
                     '<' => match self.peek() {
                         Some('<') => {
                             self.read();
-                            Token::Operator(Operator::BitwiseLeft)
+                            self.read_assign(Operator::BitwiseLeft, Operator::BitwiseLeftAssign)
                         }
                         Some('=') => {
                             self.read();
@@ -239,6 +490,16 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Reads the `=` that turns `op` into its assignment form, if it follows.
+    fn read_assign(&mut self, op: Operator, assign: Operator) -> Token {
+        if self.peek() == Some(&'=') {
+            self.read();
+            Token::Operator(assign)
+        } else {
+            Token::Operator(op)
+        }
+    }
+
     fn peek_is_whitespace(&mut self) -> bool {
         match self.peek() {
             Some(&ch) => ch.is_whitespace(),
@@ -247,29 +508,74 @@ impl<'a> Lexer<'a> {
     }
     fn skip_whitespace(&mut self) {
         while self.peek_is_whitespace() {
-            self.read();
+            if let Some(ch) = self.read() {
+                if is_line_terminator(ch) {
+                    self.newline = true;
+                }
+            }
         }
     }
     fn peek_is_char(&mut self) -> bool {
         match self.peek() {
-            Some(&ch) => return ch.is_alphabetic() || ch.is_digit(10) || ch == '_',
+            Some(&ch) => is_identifier_char(ch),
             None => false,
         }
     }
 
     fn read_indentifier(&mut self, ch: char) -> String {
-        let mut ident = String::from(ch);
-        while self.peek_is_char() {
-            let ch = self.read().unwrap(); // The loop will break if the value is none, so unwrap is safe here
+        let mut ident = String::new();
+        let mut next = Some(ch);
+        while let Some(ch) = next {
             ident.push(ch);
+            // escapes like `\u0061` or `\u{61}`
+            if ch == '\\' && self.peek() == Some(&'u') {
+                ident.push(self.read().unwrap());
+                if self.peek() == Some(&'{') {
+                    while let Some(ch) = self.read() {
+                        ident.push(ch);
+                        if ch == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            next = if self.peek_is_char() {
+                self.read()
+            } else {
+                None
+            };
         }
 
         ident
     }
 
+    fn read_regex(&mut self) -> String {
+        let mut regex = String::from('/');
+        let mut class = false;
+        while let Some(ch) = self.read() {
+            regex.push(ch);
+            match ch {
+                '\\' => {
+                    if let Some(ch) = self.read() {
+                        regex.push(ch);
+                    }
+                }
+                '[' => class = true,
+                ']' => class = false,
+                '/' if !class => break,
+                _ => {}
+            }
+        }
+        // flags
+        while self.peek_is_char() {
+            regex.push(self.read().unwrap());
+        }
+        regex
+    }
+
     fn peek_is_valid_char(&mut self, invalid: char) -> bool {
         match self.peek() {
-            Some(&ch) => return ch != invalid,
+            Some(&ch) => ch != invalid,
             None => false,
         }
     }
@@ -280,52 +586,88 @@ impl<'a> Lexer<'a> {
         while self.peek_is_valid_char(end_char) {
             let ch = self.read().unwrap();
             string.push(ch);
+            // An escaped quote doesn't end the string
+            if ch == '\\' {
+                if let Some(ch) = self.read() {
+                    string.push(ch);
+                }
+            }
         }
         self.read();
         string
     }
 
-    fn peek_is_number(&mut self) -> bool {
-        match self.peek() {
-            Some(&ch) => ch.is_numeric() || ch == '.',
-            None => false,
-        }
-    }
-
     fn read_number(&mut self, ch: char) -> String {
         let mut number = String::from(ch);
-        while self.peek_is_number() {
-            let ch = self.read().unwrap();
-            number.push(ch)
+        if ch == '0' {
+            if let Some('x') | Some('X') | Some('o') | Some('O') | Some('b') | Some('B') =
+                self.peek()
+            {
+                while self.peek_is_char() {
+                    number.push(self.read().unwrap());
+                }
+                return number;
+            }
+        }
+        let mut fraction = ch == '.';
+        let mut exponent = false;
+        while let Some(&ch) = self.peek() {
+            match ch {
+                ch if ch.is_numeric() || ch == '_' => {}
+                '.' if !fraction && !exponent => fraction = true,
+                'e' | 'E' if !exponent => exponent = true,
+                _ => break,
+            }
+            number.push(self.read().unwrap());
+            if exponent && number.ends_with(['e', 'E']) {
+                if let Some('+') | Some('-') = self.peek() {
+                    number.push(self.read().unwrap());
+                }
+            }
+        }
+        // BigInt literals
+        if self.peek() == Some(&'n') {
+            number.push(self.read().unwrap());
         }
         number
     }
 
-    fn read_comment(&mut self, t: CommentType) {
+    fn read_comment(&mut self, t: CommentType) -> Comment {
+        let mut text = String::new();
         loop {
-            let ch = self.peek();
-            match t {
-                CommentType::SingleLine => {
-                    if ch == Some(&'\n') || ch == None {
+            match (t, self.peek()) {
+                (_, None) => break,
+                (CommentType::SingleLine, Some(&ch)) if is_line_terminator(ch) => break,
+                (CommentType::MultiLine, Some('*')) => {
+                    self.read();
+                    if self.peek() == Some(&'/') {
+                        self.read();
                         break;
                     }
+                    text.push('*');
+                    continue;
                 }
-                CommentType::MultiLine => {
-                    if ch == Some(&'*') || ch == None {
-                        self.read();
-                        let ch = self.peek();
-                        if ch == Some(&'/') {
-                            self.read();
-                            break;
-                        }
-                    }
+                _ => {}
+            }
+            if let Some(ch) = self.read() {
+                if is_line_terminator(ch) {
+                    self.newline = true;
                 }
-            };
-            self.read();
+                text.push(ch);
+            }
         }
+        Comment { t, text }
     }
 }
 
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '$' | '\\' | '\u{200c}' | '\u{200d}')
+}
+
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
 impl<'a> Iterator for Lexer<'a> {
     type Item = Token;
     fn next(&mut self) -> Option<Token> {
@@ -335,3 +677,61 @@ impl<'a> Iterator for Lexer<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Lexer;
+
+    /// The tokens of `input`, written out with spaces between them.
+    fn lex(input: &str) -> String {
+        Lexer::new(input)
+            .map(|tok| tok.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn inserts_semicolons_at_line_breaks() {
+        assert_eq!(lex("a = 1\nb = 2"), "a = 1 ; b = 2");
+        assert_eq!(lex("return\nx"), "return ; x");
+        assert_eq!(lex("a\n++b"), "a ; ++ b");
+        assert_eq!(lex("a\n++\nb"), "a ; ++ b");
+        assert_eq!(lex("a\n--\nb"), "a ; -- b");
+        assert_eq!(lex("a++\nb"), "a ++ ; b");
+        assert_eq!(lex("if (a)\nb()"), "if ( a ) b ( )");
+    }
+
+    #[test]
+    fn continues_expressions_over_line_breaks() {
+        assert_eq!(lex("a\n(b)"), "a ( b )");
+        assert_eq!(lex("a\n+ b"), "a + b");
+        assert_eq!(lex("a\n.b"), "a . b");
+    }
+
+    #[test]
+    fn reads_regex_literals() {
+        assert_eq!(lex("x = /a\\/b[/]/gi"), "x = /a\\/b[/]/gi");
+        assert_eq!(lex("f(/é/)"), "f ( /é/ )");
+        assert_eq!(lex("a / b / c"), "a / b / c");
+    }
+
+    #[test]
+    fn inserts_semicolon_after_debugger() {
+        assert_eq!(lex("debugger\nfoo()"), "debugger ; foo ( )");
+        assert_eq!(lex("{debugger\n}"), "{ debugger }");
+    }
+
+    #[test]
+    fn contextual_keywords_only_continue_their_statements() {
+        assert_eq!(lex("for (const a\nof b) c"), "for ( const a of b ) c");
+        assert_eq!(lex("import a\nfrom 'b'"), "import a from 'b'");
+        assert_eq!(lex("export {a\nas b}"), "export { a as b }");
+        assert_eq!(lex("x = 1\nas = 2"), "x = 1 ; as = 2");
+        assert_eq!(lex("x = 1\nof = 2"), "x = 1 ; of = 2");
+        assert_eq!(
+            lex("import a from 'b'\nfrom = 1"),
+            "import a from 'b' ; from = 1"
+        );
+        assert_eq!(lex("for (;;) {a\nof = 1}"), "for ( ; ; ) { a ; of = 1 }");
+    }
+}
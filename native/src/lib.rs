@@ -1,14 +1,275 @@
 use neon::prelude::*;
+mod buffer;
+mod compress;
 mod lexer;
+mod mangle;
 mod minify;
+mod name_cache;
+mod options;
+mod source_map;
 mod token;
-fn minify(mut cx: FunctionContext) -> JsResult<JsString> {
+mod util;
+
+fn read_bool(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<bool>> {
+    let value = obj.get(cx, key)?;
+    if value.is_a::<JsUndefined>() {
+        return Ok(None);
+    }
+    Ok(Some(value.downcast_or_throw::<JsBoolean, _>(cx)?.value()))
+}
+
+fn read_number(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<f64>> {
+    let value = obj.get(cx, key)?;
+    if value.is_a::<JsUndefined>() {
+        return Ok(None);
+    }
+    Ok(Some(value.downcast_or_throw::<JsNumber, _>(cx)?.value()))
+}
+
+fn read_string(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<String>> {
+    let value = obj.get(cx, key)?;
+    if value.is_a::<JsUndefined>() {
+        return Ok(None);
+    }
+    Ok(Some(value.downcast_or_throw::<JsString, _>(cx)?.value()))
+}
+
+fn read_strings(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<Vec<String>>> {
+    let value = obj.get(cx, key)?;
+    if value.is_a::<JsUndefined>() {
+        return Ok(None);
+    }
+    let mut strings = Vec::new();
+    for item in value.downcast_or_throw::<JsArray, _>(cx)?.to_vec(cx)? {
+        strings.push(item.downcast_or_throw::<JsString, _>(cx)?.value());
+    }
+    Ok(Some(strings))
+}
+
+fn read_string_map(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    key: &str,
+) -> NeonResult<Option<Vec<(String, String)>>> {
+    let value = obj.get(cx, key)?;
+    if value.is_a::<JsUndefined>() {
+        return Ok(None);
+    }
+    let map = value.downcast_or_throw::<JsObject, _>(cx)?;
+    let mut entries = Vec::new();
+    for name in map.get_own_property_names(cx)?.to_vec(cx)? {
+        let name = name.downcast_or_throw::<JsString, _>(cx)?.value();
+        let value = map.get(cx, name.as_str())?;
+        entries.push((name, value.downcast_or_throw::<JsString, _>(cx)?.value()));
+    }
+    Ok(Some(entries))
+}
+
+/// Reads a regex written like a JavaScript literal, `/pattern/flags`.
+fn read_regex(literal: &str) -> Option<Result<regex::Regex, regex::Error>> {
+    let literal = literal.strip_prefix('/')?;
+    let end = literal.rfind('/')?;
+    let (pattern, flags) = (&literal[..end], &literal[end + 1..]);
+    let mut builder = regex::RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'm' => builder.multi_line(true),
+            's' => builder.dot_matches_new_line(true),
+            _ => return None,
+        };
+    }
+    Some(builder.build())
+}
+
+fn read_options(
+    cx: &mut FunctionContext,
+    obj: Handle<JsObject>,
+    input: &str,
+) -> NeonResult<options::MinifyOptions> {
+    let mut options = options::MinifyOptions::default();
+    if let Some(drop_debugger) = read_bool(cx, obj, "drop_debugger")? {
+        options.drop_debugger = drop_debugger;
+    }
+    if let Some(pure_funcs) = read_strings(cx, obj, "pure_funcs")? {
+        options.pure_funcs = pure_funcs;
+    }
+    if let Some(define) = read_string_map(cx, obj, "define")? {
+        options.define = define;
+    }
+    if let Some(dead_code) = read_bool(cx, obj, "dead_code")? {
+        options.dead_code = dead_code;
+    }
+    if let Some(fold_constants) = read_bool(cx, obj, "fold_constants")? {
+        options.fold_constants = fold_constants;
+    }
+    if let Some(directives) = read_bool(cx, obj, "directives")? {
+        options.directives = directives;
+    }
+    if let Some(module) = read_bool(cx, obj, "module")? {
+        options.module = module;
+    }
+    if let Some(conditionals) = read_bool(cx, obj, "conditionals")? {
+        options.conditionals = conditionals;
+    }
+    if let Some(join_vars) = read_bool(cx, obj, "join_vars")? {
+        options.join_vars = join_vars;
+    }
+    if let Some(drop_braces) = read_bool(cx, obj, "drop_braces")? {
+        options.drop_braces = drop_braces;
+    }
+    if let Some(redundant_punctuation) = read_bool(cx, obj, "redundant_punctuation")? {
+        options.redundant_punctuation = redundant_punctuation;
+    }
+    if let Some(arrows) = read_bool(cx, obj, "arrows")? {
+        options.arrows = arrows;
+    }
+    if let Some(comparisons) = read_bool(cx, obj, "comparisons")? {
+        options.comparisons = comparisons;
+    }
+    if let Some(shorthand) = read_bool(cx, obj, "shorthand")? {
+        options.shorthand = shorthand;
+    }
+    if let Some(drop_parens) = read_bool(cx, obj, "drop_parens")? {
+        options.drop_parens = drop_parens;
+    }
+    if let Some(mangle) = read_bool(cx, obj, "mangle")? {
+        options.mangle = mangle;
+    }
+    if let Some(toplevel) = read_bool(cx, obj, "toplevel")? {
+        options.toplevel = toplevel;
+    }
+    if let Some(mangle_props) = read_string(cx, obj, "mangle_props")? {
+        options.mangle_props = match read_regex(&mangle_props) {
+            Some(Ok(regex)) => Some(regex),
+            Some(Err(err)) => return cx.throw_error(err.to_string()),
+            None => {
+                return cx.throw_error(format!(
+                    "`mangle_props` must be a /regex/, not `{}`",
+                    mangle_props
+                ))
+            }
+        };
+    }
+    if let Some(reserved_props) = read_strings(cx, obj, "reserved_props")? {
+        options.reserved_props = reserved_props;
+    }
+    if let Some(mangle_quoted) = read_bool(cx, obj, "mangle_quoted")? {
+        options.mangle_quoted = mangle_quoted;
+    }
+    if let Some(path) = read_string(cx, obj, "name_cache")? {
+        match name_cache::NameCache::load(&path) {
+            Ok(cache) => options.name_cache = Some(cache),
+            Err(err) => return cx.throw_error(err),
+        }
+    }
+    if let Some(ecma) = read_number(cx, obj, "ecma")? {
+        options.ecma = Some(ecma as u32);
+    }
+    if let Some(source_map) = read_bool(cx, obj, "source_map")? {
+        options.source_map = source_map;
+    }
+    options.source_file = read_string(cx, obj, "source_file")?;
+    options.file = read_string(cx, obj, "file")?;
+    if let Some(sources_content) = read_bool(cx, obj, "sources_content")? {
+        options.sources_content = sources_content;
+    }
+    options.source_map_url = read_string(cx, obj, "source_map_url")?;
+    if let Some(comments) = read_string(cx, obj, "comments")? {
+        options.comments = match comments.as_str() {
+            "none" => options::Comments::None,
+            "license" => options::Comments::License,
+            "all" => options::Comments::All,
+            pattern => match read_regex(pattern) {
+                Some(Ok(regex)) => options::Comments::Matching(regex),
+                Some(Err(err)) => return cx.throw_error(err.to_string()),
+                None => {
+                    return cx.throw_error(format!(
+                        "`comments` must be none, license, all or a /regex/, not `{}`",
+                        pattern
+                    ))
+                }
+            },
+        };
+    }
+    if let Some(extract_comments) = read_bool(cx, obj, "extract_comments")? {
+        options.extract_comments = extract_comments;
+    }
+    if let Some(max_line_len) = read_number(cx, obj, "max_line_len")? {
+        options.max_line_len = Some(max_line_len as u32);
+    }
+    if let Some(ascii_only) = read_bool(cx, obj, "ascii_only")? {
+        options.ascii_only = ascii_only;
+    }
+    if let Some(html_inline) = read_bool(cx, obj, "html_inline")? {
+        options.html_inline = html_inline;
+    }
+    options.inline_script = read_bool(cx, obj, "inline_script")?;
+    if let Some(spec) = read_string(cx, obj, "input_source_map")? {
+        match source_map::load_input_map(input, &spec) {
+            Ok(map) => options.input_source_map = Some(map),
+            Err(err) => return cx.throw_error(err),
+        }
+    }
+    Ok(options)
+}
+
+fn minify(mut cx: FunctionContext) -> JsResult<JsValue> {
     let input = cx.argument::<JsString>(0)?.value();
+    let (options, name_cache_file) = match cx.argument_opt(1) {
+        Some(value) if !value.is_a::<JsUndefined>() => {
+            let obj = value.downcast_or_throw::<JsObject, _>(&mut cx)?;
+            let options = read_options(&mut cx, obj, &input)?;
+            (options, read_string(&mut cx, obj, "name_cache")?)
+        }
+        _ => (options::MinifyOptions::default(), None),
+    };
+    let returns_object = options.source_map || options.extract_comments;
     let lex = lexer::Lexer::new(&input);
-    let mut minifier = minify::Minifier::new(lex);
+    let mut minifier = minify::Minifier::with_options(lex, options);
 
-    let string = minifier.generate_string();
-    Ok(cx.string(string))
+    // Without a source map or extracted comments, the code is returned on
+    // its own
+    let output = match minifier.generate() {
+        Ok(output) => output,
+        Err(err) => return cx.throw_error(err),
+    };
+    if let (Some(file), Some(cache)) = (name_cache_file, &output.name_cache) {
+        if let Err(err) = cache.save(&file) {
+            return cx.throw_error(err);
+        }
+    }
+    if !returns_object {
+        return Ok(cx.string(output.code).upcast());
+    }
+    let result = cx.empty_object();
+    let code = cx.string(output.code);
+    result.set(&mut cx, "code", code)?;
+    if let Some(map) = output.map {
+        let map = cx.string(map.to_json());
+        result.set(&mut cx, "map", map)?;
+    }
+    if let Some(license) = output.license {
+        let license = cx.string(license);
+        result.set(&mut cx, "license", license)?;
+    }
+    Ok(result.upcast())
 }
 
 register_module!(mut cx, { cx.export_function("minify", minify) });
@@ -0,0 +1,623 @@
+/// Property names of ECMAScript built-ins and the DOM, which are never
+/// renamed since code outside the input can use them.
+pub const DOM_PROPS: &[&str] = &[
+    "BYTES_PER_ELEMENT",
+    "E",
+    "EPSILON",
+    "LN10",
+    "LN2",
+    "LOG10E",
+    "LOG2E",
+    "MAX_SAFE_INTEGER",
+    "MAX_VALUE",
+    "MIN_SAFE_INTEGER",
+    "MIN_VALUE",
+    "NEGATIVE_INFINITY",
+    "NaN",
+    "PI",
+    "POSITIVE_INFINITY",
+    "SQRT1_2",
+    "SQRT2",
+    "UTC",
+    "__defineGetter__",
+    "__defineSetter__",
+    "__lookupGetter__",
+    "__lookupSetter__",
+    "__proto__",
+    "abort",
+    "aborted",
+    "abs",
+    "accept",
+    "acos",
+    "acosh",
+    "action",
+    "activeElement",
+    "add",
+    "addEventListener",
+    "after",
+    "alert",
+    "all",
+    "allSettled",
+    "alt",
+    "altKey",
+    "anchor",
+    "any",
+    "append",
+    "appendChild",
+    "apply",
+    "arc",
+    "arguments",
+    "arrayBuffer",
+    "asin",
+    "asinh",
+    "assign",
+    "asyncIterator",
+    "at",
+    "atan",
+    "atan2",
+    "atanh",
+    "atob",
+    "attachShadow",
+    "attributes",
+    "autocomplete",
+    "autofocus",
+    "back",
+    "background",
+    "before",
+    "beginPath",
+    "bind",
+    "blob",
+    "blur",
+    "body",
+    "border",
+    "bottom",
+    "btoa",
+    "bubbles",
+    "buffer",
+    "button",
+    "buttons",
+    "byteLength",
+    "byteOffset",
+    "call",
+    "caller",
+    "cancelAnimationFrame",
+    "cancelable",
+    "captureStackTrace",
+    "catch",
+    "cause",
+    "cbrt",
+    "ceil",
+    "changedTouches",
+    "charAt",
+    "charCodeAt",
+    "checked",
+    "childElementCount",
+    "childNodes",
+    "children",
+    "classList",
+    "className",
+    "clear",
+    "clearInterval",
+    "clearTimeout",
+    "click",
+    "clientHeight",
+    "clientLeft",
+    "clientTop",
+    "clientWidth",
+    "clientX",
+    "clientY",
+    "clipboard",
+    "clone",
+    "cloneNode",
+    "close",
+    "closest",
+    "clz32",
+    "code",
+    "codePointAt",
+    "color",
+    "compareDocumentPosition",
+    "composed",
+    "concat",
+    "confirm",
+    "console",
+    "construct",
+    "constructor",
+    "contains",
+    "cookie",
+    "copyWithin",
+    "cos",
+    "cosh",
+    "create",
+    "createComment",
+    "createDocumentFragment",
+    "createElement",
+    "createElementNS",
+    "createEvent",
+    "createTextNode",
+    "crypto",
+    "cssText",
+    "ctrlKey",
+    "currentScript",
+    "currentTarget",
+    "cursor",
+    "dataset",
+    "defaultPrevented",
+    "defaultView",
+    "defineProperties",
+    "defineProperty",
+    "delete",
+    "deleteProperty",
+    "deltaMode",
+    "deltaX",
+    "deltaY",
+    "deref",
+    "description",
+    "detail",
+    "devicePixelRatio",
+    "disabled",
+    "disconnect",
+    "dispatchEvent",
+    "display",
+    "document",
+    "documentElement",
+    "domain",
+    "done",
+    "dotAll",
+    "download",
+    "drawImage",
+    "endsWith",
+    "entries",
+    "eventPhase",
+    "every",
+    "exec",
+    "exp",
+    "expm1",
+    "fetch",
+    "files",
+    "fill",
+    "fillRect",
+    "fillStyle",
+    "filter",
+    "finally",
+    "find",
+    "findIndex",
+    "findLast",
+    "findLastIndex",
+    "firstChild",
+    "firstElementChild",
+    "flags",
+    "flat",
+    "flatMap",
+    "floor",
+    "focus",
+    "font",
+    "fontSize",
+    "forEach",
+    "form",
+    "formData",
+    "forms",
+    "forward",
+    "frames",
+    "freeze",
+    "from",
+    "fromCharCode",
+    "fromCodePoint",
+    "fromEntries",
+    "fround",
+    "geolocation",
+    "get",
+    "getAttribute",
+    "getBoundingClientRect",
+    "getClientRects",
+    "getComputedStyle",
+    "getContext",
+    "getDate",
+    "getDay",
+    "getElementById",
+    "getElementsByClassName",
+    "getElementsByName",
+    "getElementsByTagName",
+    "getFloat32",
+    "getFloat64",
+    "getFullYear",
+    "getHours",
+    "getInt16",
+    "getInt32",
+    "getInt8",
+    "getItem",
+    "getMilliseconds",
+    "getMinutes",
+    "getMonth",
+    "getOwnPropertyDescriptor",
+    "getOwnPropertyDescriptors",
+    "getOwnPropertyNames",
+    "getOwnPropertySymbols",
+    "getPropertyValue",
+    "getPrototypeOf",
+    "getSeconds",
+    "getTime",
+    "getTimezoneOffset",
+    "getUTCDate",
+    "getUTCDay",
+    "getUTCFullYear",
+    "getUTCHours",
+    "getUTCMilliseconds",
+    "getUTCMinutes",
+    "getUTCMonth",
+    "getUTCSeconds",
+    "getUint16",
+    "getUint32",
+    "getUint8",
+    "getYear",
+    "global",
+    "go",
+    "groupBy",
+    "groups",
+    "has",
+    "hasAttribute",
+    "hasChildNodes",
+    "hasIndices",
+    "hasInstance",
+    "hasOwn",
+    "hasOwnProperty",
+    "hash",
+    "head",
+    "headers",
+    "height",
+    "hidden",
+    "history",
+    "host",
+    "hostname",
+    "href",
+    "htmlFor",
+    "hypot",
+    "id",
+    "ignoreCase",
+    "images",
+    "imul",
+    "includes",
+    "index",
+    "indexOf",
+    "innerHTML",
+    "innerHeight",
+    "innerText",
+    "innerWidth",
+    "input",
+    "insertAdjacentElement",
+    "insertAdjacentHTML",
+    "insertAdjacentText",
+    "insertBefore",
+    "is",
+    "isArray",
+    "isConcatSpreadable",
+    "isConnected",
+    "isEqualNode",
+    "isExtensible",
+    "isFinite",
+    "isFrozen",
+    "isInteger",
+    "isNaN",
+    "isPrototypeOf",
+    "isSafeInteger",
+    "isSealed",
+    "isTrusted",
+    "iterator",
+    "join",
+    "json",
+    "key",
+    "keyCode",
+    "keys",
+    "label",
+    "language",
+    "languages",
+    "lastChild",
+    "lastElementChild",
+    "lastIndex",
+    "lastIndexOf",
+    "left",
+    "length",
+    "lineTo",
+    "lineWidth",
+    "links",
+    "localName",
+    "localStorage",
+    "localeCompare",
+    "location",
+    "log",
+    "log10",
+    "log1p",
+    "log2",
+    "map",
+    "margin",
+    "match",
+    "matchAll",
+    "matchMedia",
+    "matches",
+    "max",
+    "maxLength",
+    "message",
+    "metaKey",
+    "method",
+    "min",
+    "minLength",
+    "moveTo",
+    "multiline",
+    "multiple",
+    "name",
+    "namespaceURI",
+    "navigator",
+    "next",
+    "nextElementSibling",
+    "nextSibling",
+    "nodeName",
+    "nodeType",
+    "nodeValue",
+    "normalize",
+    "now",
+    "observe",
+    "of",
+    "offsetHeight",
+    "offsetLeft",
+    "offsetParent",
+    "offsetTop",
+    "offsetWidth",
+    "ok",
+    "onLine",
+    "onblur",
+    "onchange",
+    "onclick",
+    "onerror",
+    "onfocus",
+    "oninput",
+    "onkeydown",
+    "onkeyup",
+    "onload",
+    "onmessage",
+    "onmousedown",
+    "onmousemove",
+    "onmouseup",
+    "onreadystatechange",
+    "onsubmit",
+    "opacity",
+    "open",
+    "opener",
+    "options",
+    "origin",
+    "outerHTML",
+    "outerHeight",
+    "outerWidth",
+    "ownKeys",
+    "ownerDocument",
+    "padEnd",
+    "padStart",
+    "padding",
+    "pageX",
+    "pageXOffset",
+    "pageY",
+    "pageYOffset",
+    "parent",
+    "parentElement",
+    "parentNode",
+    "parse",
+    "parseFloat",
+    "parseInt",
+    "password",
+    "pathname",
+    "pattern",
+    "performance",
+    "placeholder",
+    "platform",
+    "pop",
+    "port",
+    "position",
+    "postMessage",
+    "pow",
+    "prepend",
+    "preventDefault",
+    "preventExtensions",
+    "previousElementSibling",
+    "previousSibling",
+    "print",
+    "prompt",
+    "propertyIsEnumerable",
+    "protocol",
+    "prototype",
+    "push",
+    "pushState",
+    "querySelector",
+    "querySelectorAll",
+    "queueMicrotask",
+    "race",
+    "random",
+    "raw",
+    "readAsDataURL",
+    "readAsText",
+    "readOnly",
+    "readyState",
+    "redirect",
+    "reduce",
+    "reduceRight",
+    "referrer",
+    "register",
+    "reject",
+    "rel",
+    "relatedTarget",
+    "reload",
+    "remove",
+    "removeAttribute",
+    "removeChild",
+    "removeEventListener",
+    "removeItem",
+    "removeProperty",
+    "repeat",
+    "replace",
+    "replaceAll",
+    "replaceChild",
+    "replaceChildren",
+    "replaceState",
+    "replaceWith",
+    "requestAnimationFrame",
+    "requestIdleCallback",
+    "required",
+    "reset",
+    "resolve",
+    "response",
+    "responseText",
+    "responseType",
+    "result",
+    "return",
+    "reverse",
+    "revocable",
+    "right",
+    "round",
+    "screen",
+    "scripts",
+    "scroll",
+    "scrollBy",
+    "scrollHeight",
+    "scrollIntoView",
+    "scrollLeft",
+    "scrollTo",
+    "scrollTop",
+    "scrollWidth",
+    "scrollX",
+    "scrollY",
+    "seal",
+    "search",
+    "searchParams",
+    "selected",
+    "selectedIndex",
+    "self",
+    "send",
+    "serviceWorker",
+    "sessionStorage",
+    "set",
+    "setAttribute",
+    "setDate",
+    "setFloat32",
+    "setFloat64",
+    "setFullYear",
+    "setHours",
+    "setInt16",
+    "setInt32",
+    "setInt8",
+    "setInterval",
+    "setItem",
+    "setMilliseconds",
+    "setMinutes",
+    "setMonth",
+    "setProperty",
+    "setPrototypeOf",
+    "setRequestHeader",
+    "setSeconds",
+    "setTime",
+    "setTimeout",
+    "setUTCDate",
+    "setUTCFullYear",
+    "setUTCHours",
+    "setUTCMilliseconds",
+    "setUTCMinutes",
+    "setUTCMonth",
+    "setUTCSeconds",
+    "setUint16",
+    "setUint32",
+    "setUint8",
+    "shadowRoot",
+    "shift",
+    "shiftKey",
+    "sign",
+    "signal",
+    "sin",
+    "sinh",
+    "size",
+    "slice",
+    "some",
+    "sort",
+    "source",
+    "species",
+    "splice",
+    "split",
+    "sqrt",
+    "src",
+    "stack",
+    "startsWith",
+    "state",
+    "status",
+    "statusText",
+    "sticky",
+    "stopImmediatePropagation",
+    "stopPropagation",
+    "stringify",
+    "stroke",
+    "strokeStyle",
+    "structuredClone",
+    "style",
+    "subarray",
+    "submit",
+    "substr",
+    "substring",
+    "tabIndex",
+    "tagName",
+    "takeRecords",
+    "tan",
+    "tanh",
+    "target",
+    "test",
+    "text",
+    "textContent",
+    "then",
+    "throw",
+    "timeStamp",
+    "title",
+    "toDateString",
+    "toExponential",
+    "toFixed",
+    "toISOString",
+    "toJSON",
+    "toLocaleDateString",
+    "toLocaleLowerCase",
+    "toLocaleString",
+    "toLocaleTimeString",
+    "toLocaleUpperCase",
+    "toLowerCase",
+    "toPrecision",
+    "toPrimitive",
+    "toReversed",
+    "toSorted",
+    "toSpliced",
+    "toString",
+    "toStringTag",
+    "toTimeString",
+    "toUTCString",
+    "toUpperCase",
+    "toggleAttribute",
+    "top",
+    "touches",
+    "transform",
+    "transition",
+    "trim",
+    "trimEnd",
+    "trimLeft",
+    "trimRight",
+    "trimStart",
+    "trunc",
+    "type",
+    "unicode",
+    "unobserve",
+    "unregister",
+    "unscopables",
+    "unshift",
+    "url",
+    "userAgent",
+    "username",
+    "value",
+    "valueOf",
+    "values",
+    "visibility",
+    "which",
+    "width",
+    "window",
+    "with",
+    "withCredentials",
+    "zIndex",
+];
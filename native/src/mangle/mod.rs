@@ -0,0 +1,346 @@
+mod domprops;
+mod properties;
+mod scope;
+
+use std::collections::HashSet;
+
+use scope::{Form, ScopeKind, Scopes, Target};
+
+use crate::buffer::TokenBuffer;
+use crate::name_cache::NameCache;
+use crate::options::MinifyOptions;
+use crate::token::{lookup_keyword, Keyword, Operator, Token};
+
+pub use properties::mangle_properties;
+
+const FIRST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$_";
+const REST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$_0123456789";
+
+/// The `n`th shortest name, counting from `a`.
+fn short_name(mut n: usize) -> String {
+    let mut name = String::new();
+    name.push(FIRST_CHARS[n % FIRST_CHARS.len()] as char);
+    n /= FIRST_CHARS.len();
+    while n > 0 {
+        n -= 1;
+        name.push(REST_CHARS[n % REST_CHARS.len()] as char);
+        n /= REST_CHARS.len();
+    }
+    name
+}
+
+fn is_reserved(name: &str) -> bool {
+    matches!(lookup_keyword(name.to_string()), Token::Keyword(_))
+        || name == "arguments"
+        || name == "eval"
+}
+
+/// A change to the tokens, made once all the names are picked.
+enum Edit {
+    Rename(Form, String),
+    /// Drops the `export` in front of a declaration whose names changed.
+    Unexport,
+    /// Exports the declared names after the declaration, as
+    /// `export{a as foo}`.
+    Export(Vec<(String, String)>),
+}
+
+/// The identifiers in `tokens` that refer to a global rather than to a
+/// binding declared in the code, like `process` in `process.env`. Property
+/// names, bindings and shorthand properties aren't among them.
+pub fn global_references(tokens: &[Token]) -> HashSet<usize> {
+    scope::analyze(tokens)
+        .globals
+        .into_iter()
+        .filter(|use_| use_.form == Form::Plain)
+        .map(|use_| use_.token)
+        .collect()
+}
+
+/// Renames the bindings local to functions, blocks, `catch` clauses and
+/// classes to the shortest names that don't clash with anything they can
+/// see. Scopes with a direct `eval` or a `with` in them keep their names.
+/// With `toplevel`, the bindings of the program itself are renamed too,
+/// except that exports keep the names they are exported as, and the ones in
+/// `name_cache` get the names they got before.
+pub fn mangle(
+    tokens: &mut TokenBuffer,
+    options: &MinifyOptions,
+    name_cache: Option<&mut NameCache>,
+) -> Result<(), String> {
+    // names with escapes in them can't be compared by their text
+    let escaped = tokens
+        .iter()
+        .any(|tok| matches!(tok, Token::Ident(name) if name.contains('\\')));
+    if escaped {
+        return Ok(());
+    }
+    let scopes = scope::analyze(tokens);
+    let names = assign_names(&scopes, options.toplevel, name_cache.as_deref())?;
+    if let Some(cache) = name_cache {
+        for (binding, name) in scopes.bindings.iter().zip(&names) {
+            if binding.scope == 0 && *name != binding.name {
+                cache.vars.insert(binding.name.clone(), name.clone());
+            }
+        }
+    }
+    let mut edits = Vec::new();
+    for (binding, name) in scopes.bindings.iter().zip(&names) {
+        if *name == binding.name {
+            continue;
+        }
+        for use_ in &binding.uses {
+            edits.push((use_.token, Edit::Rename(use_.form, name.clone())));
+        }
+    }
+    for export in &scopes.exports {
+        let renamed = export
+            .bindings
+            .iter()
+            .any(|&id| names[id] != scopes.bindings[id].name);
+        if !renamed {
+            continue;
+        }
+        let specifiers = export
+            .bindings
+            .iter()
+            .map(|&id| (names[id].clone(), scopes.bindings[id].name.clone()))
+            .collect();
+        edits.push((export.keyword, Edit::Unexport));
+        edits.push((export.end, Edit::Export(specifiers)));
+    }
+    // from the end, so the edits don't move the tokens still to do. A token
+    // is renamed before an export is put in front of it.
+    edits.sort_by_key(|(i, edit)| std::cmp::Reverse((*i, !matches!(edit, Edit::Export(_)))));
+    for (i, edit) in edits {
+        match edit {
+            Edit::Rename(Form::Plain, name) => tokens.rename(i, name),
+            Edit::Rename(Form::Shorthand, name) => {
+                // `{a}` becomes `{a:b}`
+                let key = tokens[i].clone();
+                tokens.splice(
+                    i..i + 1,
+                    vec![key.clone(), Token::Operator(Operator::Colon), key],
+                );
+                tokens.rename(i + 2, name);
+            }
+            Edit::Rename(Form::Import, name) => {
+                let imported = tokens[i].clone();
+                tokens.splice(i..i + 1, vec![imported.clone(), as_keyword(), imported]);
+                tokens.rename(i + 2, name);
+            }
+            Edit::Rename(Form::Export, name) => {
+                let exported = tokens[i].clone();
+                tokens.splice(i..i + 1, vec![exported.clone(), as_keyword(), exported]);
+                tokens.rename(i, name);
+            }
+            Edit::Unexport => tokens.drain(i..i + 1),
+            Edit::Export(specifiers) => {
+                let mut export = Vec::new();
+                // `export const a=1` may not have ended with a semicolon
+                if i > 0
+                    && tokens[i - 1] != Token::Semicolon
+                    && tokens[i - 1] != Token::RightBracket
+                {
+                    export.push(Token::Semicolon);
+                }
+                export.push(Token::Keyword(Keyword::EXPORT));
+                export.push(Token::LeftBracket);
+                for (n, (local, exported)) in specifiers.into_iter().enumerate() {
+                    if n > 0 {
+                        export.push(Token::Comma);
+                    }
+                    if local != exported {
+                        export.push(Token::Ident(local));
+                        export.push(as_keyword());
+                    }
+                    export.push(Token::Ident(exported));
+                }
+                export.push(Token::RightBracket);
+                // nor does the `export{…}` end the statement by itself
+                if !matches!(
+                    tokens.get(i),
+                    None | Some(Token::Semicolon) | Some(Token::RightBracket)
+                ) {
+                    export.push(Token::Semicolon);
+                }
+                tokens.splice(i..i, export);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn as_keyword() -> Token {
+    Token::Ident("as".to_string())
+}
+
+/// Picks the name of each binding. Scopes are named from the outside in, and
+/// in each one the bindings used the most get the shortest names. Top-level
+/// bindings in `name_cache` take the names they had, and it is an error if
+/// one of those is no longer free.
+fn assign_names(
+    scopes: &Scopes,
+    toplevel: bool,
+    name_cache: Option<&NameCache>,
+) -> Result<Vec<String>, String> {
+    let renamable: Vec<bool> = scopes
+        .bindings
+        .iter()
+        .map(|binding| {
+            let scope = &scopes.scopes[binding.scope];
+            !binding.fixed && !scope.deopt && (toplevel || scope.kind != ScopeKind::Program)
+        })
+        .collect();
+    // names a binding can't take because a binding that keeps its name would
+    // hide it from one of its uses
+    let mut blocked: Vec<HashSet<&str>> = vec![HashSet::new(); scopes.bindings.len()];
+    for scope in &scopes.scopes {
+        let kept: Vec<&str> = scope
+            .bindings
+            .values()
+            .filter(|&&id| !renamable[id])
+            .map(|&id| scopes.bindings[id].name.as_str())
+            .collect();
+        if kept.is_empty() {
+            continue;
+        }
+        for target in &scope.through {
+            if let Target::Binding(id) = target {
+                blocked[*id].extend(kept.iter().copied());
+            }
+        }
+    }
+    let mut names: Vec<String> = scopes.bindings.iter().map(|b| b.name.clone()).collect();
+    // parents come before their children
+    for scope in &scopes.scopes {
+        let mut taken: HashSet<String> = scope
+            .through
+            .iter()
+            .map(|target| match target {
+                Target::Binding(id) => names[*id].clone(),
+                Target::Global(name) => name.clone(),
+            })
+            .collect();
+        let mut ids: Vec<usize> = scope.bindings.values().copied().collect();
+        for &id in &ids {
+            if !renamable[id] {
+                taken.insert(names[id].clone());
+            }
+        }
+        ids.retain(|&id| renamable[id]);
+        let cached = name_cache
+            .map(|cache| &cache.vars)
+            .filter(|_| scope.kind == ScopeKind::Program);
+        // names given out before are only for the bindings they were given to
+        let given_out: HashSet<&str> = cached
+            .into_iter()
+            .flat_map(|vars| vars.values().map(String::as_str))
+            .collect();
+        ids.sort_by_key(|&id| {
+            let binding = &scopes.bindings[id];
+            (std::cmp::Reverse(binding.uses.len()), binding.uses[0].token)
+        });
+        for id in ids {
+            let is_free = |name: &str| {
+                !taken.contains(name) && !blocked[id].contains(name) && !is_reserved(name)
+            };
+            let original = &scopes.bindings[id].name;
+            let name = match cached.and_then(|vars| vars.get(original)) {
+                Some(name) if is_free(name) => name.clone(),
+                Some(name) => {
+                    return Err(format!(
+                        "name cache renames `{}` to `{}`, which the code already uses",
+                        original, name
+                    ))
+                }
+                None => (0..)
+                    .map(short_name)
+                    .find(|name| is_free(name) && !given_out.contains(name.as_str()))
+                    .unwrap(),
+            };
+            taken.insert(name.clone());
+            names[id] = name;
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::short_name;
+    use crate::minify::minify_with;
+
+    #[test]
+    fn counts_through_short_names() {
+        assert_eq!(short_name(0), "a");
+        assert_eq!(short_name(53), "_");
+        assert_eq!(short_name(54), "aa");
+        assert_eq!(short_name(55), "ba");
+    }
+
+    #[test]
+    fn renames_locals() {
+        let mangle = |input| minify_with(input, |o| o.mangle = true);
+        assert_eq!(
+            mangle("function foo(alpha) { var beta = alpha; return beta }"),
+            "function foo(a){var b=a;return b}"
+        );
+        assert_eq!(
+            mangle("function f() { let x = 1; { let y = x; g(y) } }"),
+            "function f(){let a=1;{let b=a;g(b)}}"
+        );
+    }
+
+    #[test]
+    fn renames_toplevel_bindings() {
+        let mangle = |input| minify_with(input, |o| o.mangle = true);
+        assert_eq!(mangle("var foo = 1; foo"), "var foo=1;foo");
+        let mangle_toplevel = |input| {
+            minify_with(input, |o| {
+                o.mangle = true;
+                o.toplevel = true;
+            })
+        };
+        assert_eq!(mangle_toplevel("var foo = 1; foo"), "var a=1;a");
+        assert_eq!(
+            mangle_toplevel("let foo = 1; function bar() { return foo + baz }"),
+            "let a=1;function b(){return a+baz}"
+        );
+    }
+
+    #[test]
+    fn keeps_names_that_would_be_hidden() {
+        let mangle = |input| minify_with(input, |o| o.mangle = true);
+        assert_eq!(
+            mangle("var a; function f(x) { return a + x }"),
+            "var a;function f(b){return a+b}"
+        );
+        assert_eq!(
+            mangle("function f(x) { eval('x'); return x }"),
+            "function f(x){eval('x');return x}"
+        );
+    }
+
+    #[test]
+    fn exports_renamed_declarations() {
+        let mangle_toplevel = |input| {
+            minify_with(input, |o| {
+                o.mangle = true;
+                o.toplevel = true;
+            })
+        };
+        assert_eq!(
+            mangle_toplevel("export function foo() {} foo()"),
+            "function a(){}export{a as foo};a()"
+        );
+        assert_eq!(
+            mangle_toplevel("export const bar = 1; export class Baz {} bar"),
+            "const a=1;export{a as bar};class b{}export{b as Baz};a"
+        );
+        assert_eq!(
+            mangle_toplevel("export function foo() {}"),
+            "function a(){}export{a as foo}"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+
+use super::domprops::DOM_PROPS;
+use super::scope::{self, Context};
+use super::{is_reserved, short_name};
+use crate::buffer::TokenBuffer;
+use crate::name_cache::NameCache;
+use crate::options::MinifyOptions;
+use crate::token::{Keyword, Operator, StringObject, StringType, Token};
+use crate::util;
+
+/// How a property name is written, which decides how it is renamed.
+#[derive(Clone, Copy, PartialEq)]
+enum Site {
+    /// `a.b`, a key like in `{b:1}` or `{b(){}}`, or a class member.
+    Name,
+    /// `{b}`, which becomes `{c:b}`.
+    Shorthand,
+    /// A quoted key like in `{"b":1}`, or `a["b"]`.
+    Quoted,
+}
+
+/// Renames the properties whose names match `mangle_props`, everywhere they
+/// are read or written by name. Built-in names, `reserved_props` and names
+/// that are also written in quotes, unless `mangle_quoted` is set, keep
+/// theirs. Properties in `name_cache` get the names they got before, and
+/// it is an error if one of those is taken by a property that is kept.
+pub fn mangle_properties(
+    tokens: &mut TokenBuffer,
+    options: &MinifyOptions,
+    name_cache: Option<&mut NameCache>,
+) -> Result<(), String> {
+    let filter = match &options.mangle_props {
+        Some(filter) => filter,
+        None => return Ok(()),
+    };
+    let sites = find_sites(tokens);
+    let quoted: HashSet<&str> = sites
+        .iter()
+        .filter(|(_, site, _)| *site == Site::Quoted)
+        .map(|(_, _, name)| name.as_str())
+        .collect();
+    let is_kept = |name: &str| {
+        !filter.is_match(name)
+            || DOM_PROPS.binary_search(&name).is_ok()
+            || options
+                .reserved_props
+                .iter()
+                .any(|reserved| reserved == name)
+            || (!options.mangle_quoted && quoted.contains(name))
+    };
+    // the names that stay can't be given to a renamed property
+    let mut taken: HashSet<&str> = HashSet::new();
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (i, _, name) in &sites {
+        if is_kept(name) {
+            taken.insert(name);
+        } else {
+            counts.entry(name).or_insert((0, *i)).0 += 1;
+        }
+    }
+    let mut order: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+    order.sort_by_key(|&(_, (count, first))| (std::cmp::Reverse(count), first));
+    let cached = name_cache.as_deref().map(|cache| &cache.props);
+    // names given out before are only for the properties they were given to
+    let given_out: HashSet<&str> = cached
+        .into_iter()
+        .flat_map(|props| props.values().map(String::as_str))
+        .collect();
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut n = 0;
+    for (name, _) in order {
+        if let Some(cached) = cached.and_then(|props| props.get(name)) {
+            if taken.contains(cached.as_str()) {
+                return Err(format!(
+                    "name cache renames property `{}` to `{}`, which the code already uses",
+                    name, cached
+                ));
+            }
+            names.insert(name.to_string(), cached.clone());
+            continue;
+        }
+        let new_name = loop {
+            let candidate = short_name(n);
+            n += 1;
+            if !taken.contains(candidate.as_str())
+                && !given_out.contains(candidate.as_str())
+                && !is_reserved(&candidate)
+                && DOM_PROPS.binary_search(&candidate.as_str()).is_err()
+                && !options.reserved_props.contains(&candidate)
+            {
+                break candidate;
+            }
+        };
+        names.insert(name.to_string(), new_name);
+    }
+    if let Some(cache) = name_cache {
+        cache.props.extend(names.clone());
+    }
+    for (i, site, name) in sites.into_iter().rev() {
+        let new_name = match names.get(&name) {
+            Some(new_name) => new_name.clone(),
+            None => continue,
+        };
+        match site {
+            Site::Name => tokens.rename(i, new_name),
+            Site::Shorthand => {
+                let value = tokens[i].clone();
+                tokens.splice(
+                    i..i + 1,
+                    vec![value.clone(), Token::Operator(Operator::Colon), value],
+                );
+                tokens.rename(i, new_name);
+            }
+            Site::Quoted => {
+                let t = match &tokens[i] {
+                    Token::STRING(string) => string.t.clone(),
+                    _ => continue,
+                };
+                let key = Token::STRING(StringObject { value: new_name, t });
+                tokens.splice(i..i + 1, vec![key]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The property names in `tokens`, with where they are and how they are
+/// written.
+fn find_sites(tokens: &[Token]) -> Vec<(usize, Site, String)> {
+    let mut sites = Vec::new();
+    let mut class_bodies = HashSet::new();
+    let mut brackets: Vec<(usize, Context)> = Vec::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok {
+            Token::Keyword(Keyword::CLASS) => {
+                if let Some((body, _)) = scope::class_body(tokens, i) {
+                    class_bodies.insert(body);
+                }
+            }
+            Token::LeftParen | Token::LeftBrace => brackets.push((i, Context::Other)),
+            Token::LeftBracket => {
+                let context = if class_bodies.contains(&i) {
+                    Context::Class
+                } else if util::is_object_literal(tokens, i) {
+                    Context::Object
+                } else {
+                    Context::Other
+                };
+                brackets.push((i, context));
+            }
+            Token::RightParen | Token::RightBrace | Token::RightBracket => {
+                brackets.pop();
+            }
+            Token::Ident(name) if !name.starts_with('#') && !name.contains('\\') => {
+                if let Some(site) = name_site(tokens, i, brackets.last()) {
+                    sites.push((i, site, name.clone()));
+                }
+            }
+            Token::STRING(string) if string.t != StringType::Backtick => {
+                let is_name = !string.value.is_empty()
+                    && !string.value.contains('\\')
+                    && is_quoted_site(tokens, i, &brackets);
+                if is_name {
+                    sites.push((i, Site::Quoted, string.value.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+    sites
+}
+
+/// How the identifier at `i` names a property, if it does. `bracket` is the
+/// innermost bracket it is in.
+fn name_site(tokens: &[Token], i: usize, bracket: Option<&(usize, Context)>) -> Option<Site> {
+    if i > 0 && tokens[i - 1] == Token::Operator(Operator::Period) {
+        return Some(Site::Name);
+    }
+    let next = tokens.get(i + 1);
+    match bracket {
+        Some(&(open, Context::Object)) if scope::is_key_position(tokens, i, open) => match next {
+            Some(Token::Operator(Operator::Colon)) | Some(Token::LeftParen) => Some(Site::Name),
+            Some(Token::Comma)
+            | Some(Token::RightBracket)
+            | Some(Token::Operator(Operator::Assign)) => Some(Site::Shorthand),
+            // `get` or `set` in front of the key
+            _ => None,
+        },
+        Some(&(open, Context::Class)) if scope::is_member_position(tokens, i, open) => match next {
+            Some(Token::LeftParen)
+            | Some(Token::Semicolon)
+            | Some(Token::RightBracket)
+            | Some(Token::Operator(Operator::Assign)) => Some(Site::Name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the string at `i` is a quoted key or member name, like in
+/// `{"a":1}`, `{["a"]:1}` or `b["a"]`.
+fn is_quoted_site(tokens: &[Token], i: usize, brackets: &[(usize, Context)]) -> bool {
+    let next = tokens.get(i + 1);
+    match brackets.last() {
+        Some(&(open, Context::Object)) if scope::is_key_position(tokens, i, open) => {
+            return matches!(
+                next,
+                Some(Token::Operator(Operator::Colon)) | Some(Token::LeftParen)
+            );
+        }
+        Some(&(open, Context::Class)) if scope::is_member_position(tokens, i, open) => {
+            return matches!(
+                next,
+                Some(Token::LeftParen)
+                    | Some(Token::Semicolon)
+                    | Some(Token::RightBracket)
+                    | Some(Token::Operator(Operator::Assign))
+            );
+        }
+        _ => {}
+    }
+    // `b["a"]`, or a computed key
+    if i < 2 || tokens[i - 1] != Token::LeftBrace || next != Some(&Token::RightBrace) {
+        return false;
+    }
+    if tokens[i - 2].ends_expression() {
+        return true;
+    }
+    match brackets.len().checked_sub(2).map(|outer| brackets[outer]) {
+        Some((open, Context::Object)) => scope::is_key_position(tokens, i - 1, open),
+        Some((open, Context::Class)) => scope::is_member_position(tokens, i - 1, open),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::minify::minify_with;
+
+    #[test]
+    fn renames_matching_properties() {
+        let mangle_props =
+            |input| minify_with(input, |o| o.mangle_props = Some(Regex::new("^_").unwrap()));
+        assert_eq!(
+            mangle_props("x._a = 1; y._a; z._b; w.c"),
+            "x.a=1;y.a;z.b;w.c"
+        );
+        assert_eq!(
+            mangle_props("class A { _a = 1; _b() {} }"),
+            "class A{a=1;b(){}}"
+        );
+    }
+
+    #[test]
+    fn keeps_quoted_properties() {
+        let mangle_props =
+            |input| minify_with(input, |o| o.mangle_props = Some(Regex::new("^_").unwrap()));
+        assert_eq!(
+            mangle_props("x = { _a: 1, \"_b\": 2 }; x._a"),
+            "x={a:1,\"_b\":2};x.a"
+        );
+        assert_eq!(mangle_props("x[\"_a\"]"), "x[\"_a\"]");
+    }
+}
@@ -0,0 +1,801 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::token::{precedence, Keyword, Operator, Token};
+use crate::util;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScopeKind {
+    Program,
+    Function,
+    Block,
+    Catch,
+    Class,
+}
+
+/// Something a name refers to: one of the bindings, or a global that isn't
+/// declared in the code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    Binding(usize),
+    Global(String),
+}
+
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<usize>,
+    /// The token after the last one in the scope.
+    end: usize,
+    /// The `(` of a function's parameters.
+    params: Option<usize>,
+    pub bindings: HashMap<String, usize>,
+    /// Contains a direct `eval` or `with`, which can see the bindings of this
+    /// scope by their names.
+    pub deopt: bool,
+    /// Bindings from outside this scope, and globals, that are referred to
+    /// from inside it.
+    pub through: HashSet<Target>,
+}
+
+/// How a binding's name is written where it is used, which decides how it
+/// is renamed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Form {
+    Plain,
+    /// Also a property key, as in `{a}`, which becomes `{a:b}`.
+    Shorthand,
+    /// Also the imported name, as in `import{a}`, which becomes
+    /// `import{a as b}`.
+    Import,
+    /// Also the exported name, as in `export{a}`, which becomes
+    /// `export{b as a}`.
+    Export,
+}
+
+/// A place where a binding's name is written.
+#[derive(Clone, Copy, Debug)]
+pub struct Use {
+    pub token: usize,
+    pub form: Form,
+}
+
+pub struct Binding {
+    pub name: String,
+    pub scope: usize,
+    pub uses: Vec<Use>,
+    /// The name has to stay, because code that can't be renamed refers to it.
+    pub fixed: bool,
+}
+
+/// An exported declaration, like `export const a=1`.
+pub struct Export {
+    /// The `export` keyword.
+    pub keyword: usize,
+    /// The token after the declaration.
+    pub end: usize,
+    pub bindings: Vec<usize>,
+}
+
+pub struct Scopes {
+    pub scopes: Vec<Scope>,
+    pub bindings: Vec<Binding>,
+    pub exports: Vec<Export>,
+    /// Where globals that aren't declared in the code are used.
+    pub globals: Vec<Use>,
+}
+
+/// What a bracket that the walk is in holds, for telling property keys
+/// from references.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Context {
+    Object,
+    Class,
+    Other,
+}
+
+struct Analyzer<'a> {
+    tokens: &'a [Token],
+    scopes: Vec<Scope>,
+    /// The scopes the walk is in, innermost last.
+    stack: Vec<usize>,
+    brackets: Vec<(usize, Context)>,
+    /// Identifiers that declare a binding, and the scope they declare it in.
+    declared: HashMap<usize, usize>,
+    /// Identifiers that are neither bindings nor references, like the
+    /// imported names in `import {a as b}`.
+    skip: HashSet<usize>,
+    class_bodies: HashSet<usize>,
+    /// The `{` of function and `catch` bodies, which belong to the scope
+    /// that their parameters opened.
+    bodies: HashSet<usize>,
+    /// Identifiers in import and export lists that name a binding and an
+    /// imported or exported name at once.
+    forms: HashMap<usize, Form>,
+    /// The `export` keyword and end of each exported declaration.
+    exports: Vec<(usize, usize)>,
+    declarations: Vec<(usize, usize, Form)>,
+    references: Vec<(usize, usize, Form)>,
+    /// Names used where they can't be renamed, like inside a template's
+    /// `${}`.
+    pinned: Vec<(String, usize)>,
+}
+
+/// Works out the scopes of `tokens`, the bindings declared in each one and
+/// where they are used. Names are compared by their text, escapes and all.
+pub fn analyze(tokens: &[Token]) -> Scopes {
+    let mut analyzer = Analyzer {
+        tokens,
+        scopes: Vec::new(),
+        stack: Vec::new(),
+        brackets: Vec::new(),
+        declared: HashMap::new(),
+        skip: HashSet::new(),
+        class_bodies: HashSet::new(),
+        bodies: HashSet::new(),
+        forms: HashMap::new(),
+        exports: Vec::new(),
+        declarations: Vec::new(),
+        references: Vec::new(),
+        pinned: Vec::new(),
+    };
+    analyzer.push(ScopeKind::Program, tokens.len(), None);
+    analyzer.walk();
+    analyzer.resolve()
+}
+
+impl<'a> Analyzer<'a> {
+    fn current(&self) -> usize {
+        *self.stack.last().unwrap()
+    }
+
+    fn push(&mut self, kind: ScopeKind, end: usize, params: Option<usize>) -> usize {
+        let id = self.scopes.len();
+        self.scopes.push(Scope {
+            kind,
+            parent: self.stack.last().copied(),
+            end,
+            params,
+            bindings: HashMap::new(),
+            deopt: false,
+            through: HashSet::new(),
+        });
+        self.stack.push(id);
+        id
+    }
+
+    /// The scope that `var` declarations in the current one belong to.
+    fn function_scope(&self) -> usize {
+        *self
+            .stack
+            .iter()
+            .rev()
+            .find(|&&id| {
+                matches!(
+                    self.scopes[id].kind,
+                    ScopeKind::Function | ScopeKind::Program
+                )
+            })
+            .unwrap()
+    }
+
+    fn walk(&mut self) {
+        let tokens = self.tokens;
+        for (i, tok) in tokens.iter().enumerate() {
+            while self.stack.len() > 1 && self.scopes[self.current()].end <= i {
+                self.stack.pop();
+            }
+            match tok {
+                Token::Keyword(Keyword::FUNCTION) => self.function(i),
+                Token::Keyword(Keyword::CLASS) => self.class(i),
+                Token::Keyword(Keyword::VAR) => self.declaration(i, self.function_scope()),
+                Token::Keyword(Keyword::LET) | Token::Keyword(Keyword::CONST) => {
+                    self.declaration(i, self.current())
+                }
+                Token::Keyword(Keyword::FOR) => self.for_loop(i),
+                Token::Keyword(Keyword::IMPORT) => self.import(i),
+                Token::Keyword(Keyword::EXPORT) => self.export(i),
+                Token::Keyword(Keyword::WITH) => {
+                    let current = self.current();
+                    self.scopes[current].deopt = true;
+                }
+                Token::LeftParen => {
+                    self.paren(i);
+                    self.brackets.push((i, Context::Other));
+                }
+                Token::LeftBrace => self.brackets.push((i, Context::Other)),
+                Token::LeftBracket => {
+                    let context = self.bracket(i);
+                    self.brackets.push((i, context));
+                }
+                Token::RightParen | Token::RightBrace | Token::RightBracket => {
+                    self.brackets.pop();
+                }
+                Token::STRING(string) if string.value.contains("${") => {
+                    let current = self.current();
+                    for name in template_names(&string.value) {
+                        self.pinned.push((name, current));
+                    }
+                }
+                Token::Ident(_) => self.ident(i),
+                _ => {}
+            }
+        }
+    }
+
+    /// Opens the scope of a `function` and declares its name and parameters.
+    fn function(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let mut k = i + 1;
+        if tokens.get(k) == Some(&Token::Operator(Operator::Asterisk)) {
+            k += 1;
+        }
+        let name = match tokens.get(k) {
+            Some(Token::Ident(_)) => {
+                k += 1;
+                Some(k - 1)
+            }
+            _ => None,
+        };
+        let (close, body_close) = match function_parts(tokens, k) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let outer = self.current();
+        let scope = self.push(ScopeKind::Function, body_close + 1, Some(k));
+        if let Some(name) = name {
+            let declared_in = if is_declaration(tokens, i) {
+                // a function declared in a block can be seen outside of it
+                // in sloppy mode, so its name is kept
+                let kind = self.scopes[outer].kind;
+                if kind != ScopeKind::Function && kind != ScopeKind::Program {
+                    self.pinned.push((ident_name(&tokens[name]), outer));
+                }
+                outer
+            } else {
+                scope
+            };
+            self.declared.insert(name, declared_in);
+        }
+        self.params(k, close, scope);
+        self.bodies.insert(close + 1);
+    }
+
+    /// Opens the scope of a class and declares its name.
+    fn class(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let name = match tokens.get(i + 1) {
+            Some(Token::Ident(_)) => Some(i + 1),
+            _ => None,
+        };
+        let (body, body_close) = match class_body(tokens, i) {
+            Some(body) => body,
+            None => return,
+        };
+        let outer = self.current();
+        let scope = self.push(ScopeKind::Class, body_close + 1, None);
+        if let Some(name) = name {
+            let declared_in = if is_declaration(tokens, i) {
+                outer
+            } else {
+                scope
+            };
+            self.declared.insert(name, declared_in);
+        }
+        self.class_bodies.insert(body);
+    }
+
+    /// Declares the names bound by a `var`, `let` or `const` at `i` in
+    /// `scope`.
+    fn declaration(&mut self, i: usize, scope: usize) {
+        let tokens = self.tokens;
+        let end = util::declaration_end(tokens, i + 1);
+        for range in util::split_top_level(&tokens[i + 1..end]) {
+            let start = i + 1 + range.start;
+            let end = i + 1 + range.end;
+            let target = find_top_level(tokens, start, end, &Token::Operator(Operator::Assign));
+            self.pattern(start, target.unwrap_or(end), scope);
+        }
+    }
+
+    /// `for` loops with a `let` or `const` in their head get a scope of
+    /// their own.
+    fn for_loop(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let mut open = i + 1;
+        if tokens.get(open) == Some(&Token::Keyword(Keyword::AWAIT)) {
+            open += 1;
+        }
+        if tokens.get(open) != Some(&Token::LeftParen) {
+            return;
+        }
+        if matches!(
+            tokens.get(open + 1),
+            Some(Token::Keyword(Keyword::LET)) | Some(Token::Keyword(Keyword::CONST))
+        ) {
+            let end = util::statement_end(tokens, i);
+            self.push(ScopeKind::Block, end, None);
+        }
+    }
+
+    fn import(&mut self, i: usize) {
+        let tokens = self.tokens;
+        // `import()` and `import.meta` are expressions
+        if matches!(
+            tokens.get(i + 1),
+            Some(Token::LeftParen) | Some(Token::Operator(Operator::Period))
+        ) {
+            return;
+        }
+        let end = util::statement_end(tokens, i);
+        let scope = self.current();
+        for k in i + 1..end {
+            if let Token::Ident(_) = &tokens[k] {
+                // the local names are the ones right before a `,`, a `}` or
+                // `from`
+                let local = match tokens.get(k + 1) {
+                    Some(Token::Comma) | Some(Token::RightBracket) => true,
+                    Some(Token::Ident(next)) => next == "from",
+                    _ => false,
+                };
+                if local {
+                    self.declared.insert(k, scope);
+                    if matches!(tokens[k - 1], Token::LeftBracket | Token::Comma) {
+                        self.forms.insert(k, Form::Import);
+                    }
+                } else {
+                    self.skip.insert(k);
+                }
+            }
+        }
+    }
+
+    fn export(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let end = util::statement_end(tokens, i);
+        match tokens.get(i + 1) {
+            Some(Token::LeftBracket) => {
+                let close = match util::matching_close(tokens, i + 1) {
+                    Some(close) => close,
+                    None => return,
+                };
+                let reexport =
+                    matches!(tokens.get(close + 1), Some(Token::Ident(name)) if name == "from");
+                // in `export{a as b}`, only `a` refers to a binding
+                for k in i + 2..end {
+                    let local = !reexport
+                        && matches!(tokens[k - 1], Token::LeftBracket | Token::Comma)
+                        && k < close;
+                    if !local {
+                        self.skip.insert(k);
+                    } else if matches!(tokens[k + 1], Token::Comma | Token::RightBracket) {
+                        self.forms.insert(k, Form::Export);
+                    }
+                }
+            }
+            Some(Token::Operator(Operator::Asterisk)) => {
+                self.skip.extend(i + 2..end);
+            }
+            Some(Token::Keyword(Keyword::VAR))
+            | Some(Token::Keyword(Keyword::LET))
+            | Some(Token::Keyword(Keyword::CONST))
+            | Some(Token::Keyword(Keyword::FUNCTION))
+            | Some(Token::Keyword(Keyword::ASYNC))
+            | Some(Token::Keyword(Keyword::CLASS)) => self.exports.push((i, end)),
+            _ => {}
+        }
+    }
+
+    /// Opens the scope of an arrow function, method or `catch` clause whose
+    /// parameters start at `i`.
+    fn paren(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let current = self.current();
+        if self.scopes[current].params == Some(i) {
+            return;
+        }
+        let close = match util::matching_close(tokens, i) {
+            Some(close) => close,
+            None => return,
+        };
+        match tokens.get(close + 1) {
+            Some(Token::Operator(Operator::Arrow)) => {
+                let end = arrow_end(tokens, close + 1);
+                let scope = self.push(ScopeKind::Function, end, Some(i));
+                self.params(i, close, scope);
+                self.bodies.insert(close + 2);
+            }
+            Some(Token::LeftBracket) if util::is_function_body(tokens, close + 1) => {
+                let body_close = match util::matching_close(tokens, close + 1) {
+                    Some(body_close) => body_close,
+                    None => return,
+                };
+                let scope = self.push(ScopeKind::Function, body_close + 1, Some(i));
+                self.params(i, close, scope);
+                self.bodies.insert(close + 1);
+            }
+            Some(Token::LeftBracket)
+                if i > 0 && tokens[i - 1] == Token::Keyword(Keyword::CATCH) =>
+            {
+                let body_close = match util::matching_close(tokens, close + 1) {
+                    Some(body_close) => body_close,
+                    None => return,
+                };
+                let scope = self.push(ScopeKind::Catch, body_close + 1, Some(i));
+                self.pattern(i + 1, close, scope);
+                self.bodies.insert(close + 1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens a block scope for the `{` at `i` if it starts a block, and says
+    /// what it holds.
+    fn bracket(&mut self, i: usize) -> Context {
+        if self.class_bodies.contains(&i) {
+            return Context::Class;
+        }
+        if self.bodies.contains(&i) {
+            return Context::Other;
+        }
+        if util::is_object_literal(self.tokens, i) {
+            return Context::Object;
+        }
+        if let Some(close) = util::matching_close(self.tokens, i) {
+            self.push(ScopeKind::Block, close + 1, None);
+        }
+        Context::Other
+    }
+
+    fn ident(&mut self, i: usize) {
+        let tokens = self.tokens;
+        let name = match &tokens[i] {
+            Token::Ident(name) => name,
+            _ => return,
+        };
+        if self.skip.contains(&i) || name.starts_with('#') {
+            return;
+        }
+        let next = tokens.get(i + 1);
+        // `x=>x` opens the function with its parameter
+        if next == Some(&Token::Operator(Operator::Arrow)) {
+            let end = arrow_end(tokens, i + 1);
+            let scope = self.push(ScopeKind::Function, end, None);
+            self.declared.insert(i, scope);
+            self.bodies.insert(i + 2);
+        }
+        let prev = if i > 0 { tokens.get(i - 1) } else { None };
+        if prev == Some(&Token::Operator(Operator::Period)) {
+            return;
+        }
+        let form = match self.brackets.last() {
+            Some(&(open, Context::Object)) if is_key_position(tokens, i, open) => {
+                if !matches!(
+                    next,
+                    Some(Token::Comma)
+                        | Some(Token::RightBracket)
+                        | Some(Token::Operator(Operator::Assign))
+                ) {
+                    // a key, or `get` in front of one
+                    return;
+                }
+                Form::Shorthand
+            }
+            Some(&(open, Context::Class)) if is_member_position(tokens, i, open) => return,
+            _ => self.forms.get(&i).copied().unwrap_or(Form::Plain),
+        };
+        if let Some(&scope) = self.declared.get(&i) {
+            self.declarations.push((i, scope, form));
+            return;
+        }
+        let label = next == Some(&Token::Operator(Operator::Colon))
+            && form == Form::Plain
+            && util::is_statement_start(tokens, i);
+        let jump = matches!(
+            prev,
+            Some(Token::Keyword(Keyword::BREAK)) | Some(Token::Keyword(Keyword::CONTINUE))
+        );
+        // `of` in `for(a of b)`
+        let contextual = name == "of" && prev.is_some_and(Token::ends_expression);
+        if label || jump || contextual {
+            return;
+        }
+        let current = self.current();
+        if name == "eval" {
+            self.scopes[current].deopt = true;
+        }
+        self.references.push((i, current, form));
+    }
+
+    /// Declares the parameters between the parentheses at `open` and
+    /// `close` in `scope`.
+    fn params(&mut self, open: usize, close: usize, scope: usize) {
+        let tokens = self.tokens;
+        for range in util::split_top_level(&tokens[open + 1..close]) {
+            let mut start = open + 1 + range.start;
+            let end = open + 1 + range.end;
+            if tokens[start] == Token::Operator(Operator::Spread) {
+                start += 1;
+            }
+            let target = find_top_level(tokens, start, end, &Token::Operator(Operator::Assign));
+            self.pattern(start, target.unwrap_or(end), scope);
+        }
+    }
+
+    /// Declares the names bound by the destructuring pattern or plain name
+    /// in `start..end`.
+    fn pattern(&mut self, start: usize, end: usize, scope: usize) {
+        let tokens = self.tokens;
+        if start >= end {
+            return;
+        }
+        match &tokens[start] {
+            Token::Ident(_) => {
+                self.declared.insert(start, scope);
+            }
+            Token::LeftBrace | Token::LeftBracket => {
+                let close = match util::matching_close(tokens, start) {
+                    Some(close) => close,
+                    None => return,
+                };
+                let object = tokens[start] == Token::LeftBracket;
+                for range in util::split_top_level(&tokens[start + 1..close]) {
+                    let mut from = start + 1 + range.start;
+                    let to = start + 1 + range.end;
+                    if from >= to {
+                        continue;
+                    }
+                    if tokens[from] == Token::Operator(Operator::Spread) {
+                        from += 1;
+                    } else if object {
+                        // the value of `key: value`, or a shorthand
+                        if let Some(colon) =
+                            find_top_level(tokens, from, to, &Token::Operator(Operator::Colon))
+                        {
+                            from = colon + 1;
+                        }
+                    }
+                    let target =
+                        find_top_level(tokens, from, to, &Token::Operator(Operator::Assign));
+                    self.pattern(from, target.unwrap_or(to), scope);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ties the declarations and references to bindings.
+    fn resolve(mut self) -> Scopes {
+        let mut bindings: Vec<Binding> = Vec::new();
+        for &(token, scope, form) in &self.declarations {
+            let name = ident_name(&self.tokens[token]);
+            let id = match self.scopes[scope].bindings.get(&name) {
+                Some(&id) => id,
+                None => {
+                    self.scopes[scope]
+                        .bindings
+                        .insert(name.clone(), bindings.len());
+                    bindings.push(Binding {
+                        name,
+                        scope,
+                        uses: Vec::new(),
+                        fixed: false,
+                    });
+                    bindings.len() - 1
+                }
+            };
+            bindings[id].uses.push(Use { token, form });
+        }
+        let mut globals = Vec::new();
+        let references = std::mem::take(&mut self.references);
+        for (token, scope, form) in references {
+            let name = ident_name(&self.tokens[token]);
+            match self.refer(name, scope) {
+                Target::Binding(id) => bindings[id].uses.push(Use { token, form }),
+                Target::Global(_) => globals.push(Use { token, form }),
+            }
+        }
+        let pinned = std::mem::take(&mut self.pinned);
+        for (name, scope) in pinned {
+            if let Target::Binding(id) = self.refer(name, scope) {
+                bindings[id].fixed = true;
+            }
+        }
+        for id in 0..self.scopes.len() {
+            if self.scopes[id].deopt {
+                let mut scope = self.scopes[id].parent;
+                while let Some(parent) = scope {
+                    self.scopes[parent].deopt = true;
+                    scope = self.scopes[parent].parent;
+                }
+            }
+        }
+        let exports = self
+            .exports
+            .iter()
+            .map(|&(keyword, end)| {
+                let mut ids = Vec::new();
+                for &(token, scope, _) in &self.declarations {
+                    if token > keyword && token < end && scope == 0 {
+                        let id = self.scopes[0].bindings[&ident_name(&self.tokens[token])];
+                        if !ids.contains(&id) {
+                            ids.push(id);
+                        }
+                    }
+                }
+                Export {
+                    keyword,
+                    end,
+                    bindings: ids,
+                }
+            })
+            .collect();
+        Scopes {
+            scopes: self.scopes,
+            bindings,
+            exports,
+            globals,
+        }
+    }
+
+    /// Finds what `name` refers to from `scope`, noting it in the `through`
+    /// of each scope on the way.
+    fn refer(&mut self, name: String, scope: usize) -> Target {
+        let mut path = Vec::new();
+        let mut current = Some(scope);
+        let target = loop {
+            match current {
+                Some(id) => match self.scopes[id].bindings.get(&name) {
+                    Some(&binding) => break Target::Binding(binding),
+                    None => {
+                        path.push(id);
+                        current = self.scopes[id].parent;
+                    }
+                },
+                None => break Target::Global(name),
+            }
+        };
+        for id in path {
+            self.scopes[id].through.insert(target.clone());
+        }
+        target
+    }
+}
+
+fn ident_name(tok: &Token) -> String {
+    match tok {
+        Token::Ident(name) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+/// The `)` closing the parameters that start at `open` and the `}` closing
+/// the body after them.
+fn function_parts(tokens: &[Token], open: usize) -> Option<(usize, usize)> {
+    if tokens.get(open) != Some(&Token::LeftParen) {
+        return None;
+    }
+    let close = util::matching_close(tokens, open)?;
+    if tokens.get(close + 1) != Some(&Token::LeftBracket) {
+        return None;
+    }
+    let body_close = util::matching_close(tokens, close + 1)?;
+    Some((close, body_close))
+}
+
+/// Where the arrow function whose `=>` is at `arrow` ends.
+fn arrow_end(tokens: &[Token], arrow: usize) -> usize {
+    if tokens.get(arrow + 1) == Some(&Token::LeftBracket) {
+        if let Some(close) = util::matching_close(tokens, arrow + 1) {
+            return close + 1;
+        }
+    }
+    util::operand_end(tokens, arrow + 1, precedence::ASSIGNMENT)
+}
+
+/// The `{` and `}` around the body of the class whose `class` keyword is at
+/// `i`.
+pub(super) fn class_body(tokens: &[Token], i: usize) -> Option<(usize, usize)> {
+    let mut body = i + 1;
+    while body < tokens.len() && tokens[body] != Token::LeftBracket {
+        body = match &tokens[body] {
+            Token::LeftParen | Token::LeftBrace => util::matching_close(tokens, body)? + 1,
+            _ => body + 1,
+        };
+    }
+    Some((body, util::matching_close(tokens, body)?))
+}
+
+/// Whether the `function` or `class` at `i` is a declaration, including
+/// exported ones.
+fn is_declaration(tokens: &[Token], i: usize) -> bool {
+    let mut start = i;
+    if start > 0 && tokens[start - 1] == Token::Keyword(Keyword::ASYNC) {
+        start -= 1;
+    }
+    if start > 0 && tokens[start - 1] == Token::Keyword(Keyword::DEFAULT) {
+        start -= 1;
+    }
+    util::is_statement_start(tokens, start)
+        || (start > 0 && tokens[start - 1] == Token::Keyword(Keyword::EXPORT))
+}
+
+/// Finds `target` in `start..end`, outside of any brackets.
+fn find_top_level(tokens: &[Token], start: usize, end: usize, target: &Token) -> Option<usize> {
+    let mut i = start;
+    while i < end {
+        match &tokens[i] {
+            Token::LeftParen | Token::LeftBrace | Token::LeftBracket => {
+                i = util::matching_close(tokens, i)?;
+            }
+            tok if tok == target => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether the identifier at `i` is where a key goes in the object literal
+/// opened at `open`, possibly after `get`, `set`, `async` or `*`.
+pub(super) fn is_key_position(tokens: &[Token], i: usize, open: usize) -> bool {
+    match &tokens[i - 1] {
+        Token::Comma => true,
+        Token::LeftBracket => i - 1 == open,
+        Token::Ident(name) if name == "get" || name == "set" => {
+            is_key_position(tokens, i - 1, open)
+        }
+        Token::Keyword(Keyword::ASYNC) | Token::Operator(Operator::Asterisk) => {
+            is_key_position(tokens, i - 1, open)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the identifier at `i` names a member of the class whose body is
+/// opened at `open`, rather than being part of a field's value.
+pub(super) fn is_member_position(tokens: &[Token], i: usize, open: usize) -> bool {
+    match &tokens[i - 1] {
+        Token::Semicolon | Token::RightBracket => true,
+        Token::LeftBracket => i - 1 == open,
+        Token::Ident(name) if name == "get" || name == "set" => {
+            is_member_position(tokens, i - 1, open)
+        }
+        Token::Keyword(Keyword::STATIC)
+        | Token::Keyword(Keyword::ASYNC)
+        | Token::Operator(Operator::Asterisk) => is_member_position(tokens, i - 1, open),
+        _ => false,
+    }
+}
+
+/// The words in the `${}` parts of a template, which can't be renamed since
+/// the lexer reads the whole template as one token.
+fn template_names(template: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut word = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if depth == 0 {
+            if ch == '\\' {
+                chars.next();
+            } else if ch == '$' && chars.peek() == Some(&'{') {
+                chars.next();
+                depth = 1;
+            }
+            continue;
+        }
+        if ch.is_alphanumeric() || ch == '_' || ch == '$' {
+            word.push(ch);
+            continue;
+        }
+        if !word.is_empty() && !word.starts_with(|ch: char| ch.is_ascii_digit()) {
+            names.push(std::mem::take(&mut word));
+        }
+        word.clear();
+        match ch {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    names
+}
@@ -1,186 +1,588 @@
+use super::buffer::{Position, TokenBuffer};
+use super::compress;
 use super::lexer::Lexer;
-use super::token::{Keyword, Operator, Token};
-
-use std::fmt::Display;
-use std::iter::Peekable;
+use super::mangle;
+use super::name_cache::NameCache;
+use super::options::MinifyOptions;
+use super::source_map::{Mapping, SourceMap};
+use super::token::{precedence, Comment, CommentType, Keyword, Operator, Token};
+use super::util;
 
 pub struct Minifier<'a> {
-    lex: Peekable<Lexer<'a>>,
+    lex: Lexer<'a>,
+    options: MinifyOptions,
 }
 
-impl<'a> Minifier<'a> {
-    pub fn new(lex: Lexer) -> Minifier {
-        Minifier {
-            lex: lex.peekable(),
+/// The minified code, along with its source map if one was asked for.
+pub struct Output {
+    pub code: String,
+    pub map: Option<SourceMap>,
+    /// The license comments moved out of the code by `extract_comments`.
+    pub license: Option<String>,
+    /// The `name_cache` option, with the names given out by this run added.
+    pub name_cache: Option<NameCache>,
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.chars().map(|ch| ch.len_utf16() as u32).sum()
+}
+
+/// Escapes the non-ASCII characters in a string, template or regex literal
+/// as UTF-16 `\uXXXX` escapes.
+fn escape_literal(text: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                // a line continuation, which stands for nothing
+                Some('\u{2028}') | Some('\u{2029}') => {}
+                // `\é` is an escape for `é`
+                Some(ch) if !ch.is_ascii() => escape_utf16(&mut escaped, ch),
+                Some(ch) => {
+                    escaped.push('\\');
+                    escaped.push(ch);
+                }
+                None => escaped.push('\\'),
+            }
+        } else if ch.is_ascii() {
+            escaped.push(ch);
+        } else {
+            escape_utf16(&mut escaped, ch);
         }
     }
+    escaped
+}
 
-    fn handle_keyword<T>(value: T, keyword: &Keyword) -> String
-    where
-        T: Display,
-    {
-        match keyword {
-            Keyword::IN => format!("{} ", value),
-            Keyword::INSTANCEOF => format!("{} ", value),
-            _ => format!("{};", value),
+fn escape_utf16(escaped: &mut String, ch: char) {
+    for unit in ch.encode_utf16(&mut [0; 2]) {
+        escaped.push_str(&format!("\\u{:04x}", unit));
+    }
+}
+
+/// Breaks up `</script` and `<!--` in a literal or comment, which would end
+/// the `<script>` element the code is inlined into. Regexes get `\x3c` rather
+/// than `\!`, which isn't a valid escape in `u` mode.
+fn escape_inline_script(text: &str, regex: bool) -> String {
+    let mut escaped = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('<') {
+        escaped.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let closes_script = rest
+            .get(..7)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case("/script"));
+        let backslashes = escaped.len() - escaped.trim_end_matches('\\').len();
+        if rest.starts_with("!--") && regex {
+            // An escaped `\<` only needs the rest of `\x3c`
+            escaped.push_str(if backslashes % 2 == 1 { "x3c" } else { "\\x3c" });
+        } else if rest.starts_with("!--") || closes_script {
+            escaped.push_str("<\\");
+        } else {
+            escaped.push('<');
         }
     }
+    escaped.push_str(rest);
+    escaped
+}
 
-    pub fn generate_string(&mut self) -> String {
-        let mut code = String::new();
-        loop {
-            let tok = self.lex.next();
-            match tok {
-                None => break,
-                Some(tok) => match tok {
-                    Token::LeftParen => code.push('('),
-                    Token::RightParen => code.push_str(&self.build_rule_string(')')),
-                    Token::LeftBrace => code.push('['),
-                    Token::RightBrace => code.push_str(&self.build_rule_string(']')),
-                    Token::LeftBracket => code.push('{'),
-                    Token::RightBracket => code.push_str(&self.build_rule_string('}')),
-                    Token::Comma => code.push(','),
-                    Token::STRING(string) => match self.lex.peek() {
-                        Some(Token::Ident(_)) => code.push_str(&format!("{};", string)),
-                        Some(Token::Keyword(keyword)) => {
-                            code.push_str(&Self::handle_keyword(string, keyword))
-                        }
-                        _ => code.push_str(&format!("{}", string)),
-                    },
-                    Token::NUMBER(number) => match self.lex.peek() {
-                        Some(Token::Ident(_)) => {
-                            code.push_str(&format!("{};", number));
-                        }
-                        Some(Token::Keyword(keyword)) => {
-                            code.push_str(&Self::handle_keyword(number, keyword))
-                        }
-                        _ => code.push_str(&format!("{}", number)),
-                    },
-
-                    Token::Ident(value) => match self.lex.peek() {
-                        Some(Token::Keyword(keyword)) => {
-                            code.push_str(&Self::handle_keyword(value, keyword))
-                        }
-                        Some(Token::Ident(_)) => code.push_str(&format!("{};", value)),
-                        _ => code.push_str(&value),
-                    },
-                    Token::Operator(op) => {
-                        let code_value = match op {
-                            Operator::Arrow => "=>",
-                            Operator::Assign => "=",
-                            Operator::Asterisk => "*",
-                            Operator::AsteriskAssign => "*=",
-                            Operator::Bang => "!",
-                            Operator::BitwiseAnd => "&",
-                            Operator::BitwiseAndAssign => "&=",
-                            Operator::BitwiseLeft => "<<",
-                            Operator::BitwiseNot => "~",
-                            Operator::BitwiseOr => "|",
-                            Operator::BitwiseOrAssign => "|=",
-                            Operator::BitwiseRight => ">>",
-                            Operator::BitwiseXor => "^",
-                            Operator::Colon => ":",
-                            Operator::Equal => "==",
-                            Operator::Exponent => "**",
-                            Operator::ExponentAssign => "**=",
-                            Operator::GreaterThan => ">",
-                            Operator::GreaterThanEqual => ">=",
-                            Operator::LessThan => "<",
-                            Operator::LessThanEqual => "<=",
-                            Operator::LogicalAnd => "&&",
-                            Operator::LogicalOr => "||",
-                            Operator::Minus => "-",
-                            Operator::MinusAssign => "-=",
-                            Operator::MinusMinus => "--",
-                            Operator::Modulo => "%",
-                            Operator::ModuloAssign => "%=",
-                            Operator::NotEqual => "!=",
-                            Operator::NullishCoalescing => "??",
-                            Operator::Period => ".",
-                            Operator::Plus => "+",
-                            Operator::PlusAssign => "+=",
-                            Operator::PlusPlus => "++",
-                            Operator::Slash => "/",
-                            Operator::SlashAssign => "/=",
-                            Operator::Spread => "...",
-                            Operator::StrictEqual => "===",
-                            Operator::StrictNotEqual => "!==",
-                            Operator::Ternary => "?",
-                            Operator::UnsignedBitwiseRight => ">>>",
-                        };
-
-                        code.push_str(code_value);
-                    }
-                    Token::Keyword(keyword) => match keyword {
-                        Keyword::ASYNC => code.push_str("async "),
-                        Keyword::AWAIT => code.push_str("await "),
-                        Keyword::BREAK => code.push_str("break"),
-                        Keyword::CASE => code.push_str("case "),
-                        Keyword::CATCH => code.push_str("catch"),
-                        Keyword::CLASS => code.push_str("class "),
-                        Keyword::CONST => code.push_str("const "),
-                        Keyword::CONTINUE => code.push_str("continue "),
-                        Keyword::DEBUGGER => code.push_str("debugger "),
-                        Keyword::DEFAULT => code.push_str("default "),
-                        Keyword::DELETE => code.push_str("delete "),
-                        Keyword::DO => code.push_str("do "),
-                        Keyword::ELSE => code.push_str("else "),
-                        Keyword::ENUM => code.push_str("enum "),
-                        Keyword::EXPORT => code.push_str("export "),
-                        Keyword::EXTENDS => code.push_str("extends "),
-                        Keyword::FALSE => code.push_str("false"),
-                        Keyword::FINALLY => code.push_str("finally "),
-                        Keyword::FOR => code.push_str("for"),
-                        Keyword::FUNCTION => code.push_str("function "),
-                        Keyword::IF => code.push_str("if"),
-                        Keyword::IMPLEMENTS => code.push_str("implements "),
-                        Keyword::IMPORT => code.push_str("import "),
-                        Keyword::IN => code.push_str("in "),
-                        Keyword::INSTANCEOF => code.push_str("instanceof "),
-                        Keyword::INTERFACE => code.push_str("interface "),
-                        Keyword::LET => code.push_str("let "),
-                        Keyword::NEW => code.push_str("new "),
-                        Keyword::NULL => code.push_str("null"),
-                        Keyword::PACKAGE => code.push_str("package"),
-                        Keyword::PRIVATE => code.push_str("private "),
-                        Keyword::PROTECTED => code.push_str("protected "),
-                        Keyword::PUBLIC => code.push_str("public "),
-                        Keyword::RETURN => code.push_str("return "),
-                        Keyword::SUPER => code.push_str("super "),
-                        Keyword::SWITCH => code.push_str("switch"),
-                        Keyword::STATIC => code.push_str("static "),
-                        Keyword::THIS => code.push_str("this"),
-                        Keyword::THROW => code.push_str("throw "),
-                        Keyword::TRY => code.push_str("try"),
-                        Keyword::TRUE => code.push_str("true"),
-                        Keyword::TYPEOF => code.push_str("typeof "),
-                        Keyword::VAR => code.push_str("var "),
-                        Keyword::VOID => code.push_str("void"),
-                        Keyword::WHILE => code.push_str("while"),
-                        Keyword::WITH => code.push_str("with"),
-                        Keyword::YIELD => code.push_str("yield "),
-                    },
-                    _ => {}
-                },
+/// Escapes the non-ASCII characters in an identifier. Surrogate pairs aren't
+/// allowed there, so characters outside the BMP take the `\u{...}` form.
+fn escape_identifier(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if ch.is_ascii() {
+            escaped.push(ch);
+        } else if (ch as u32) < 0x10000 {
+            escaped.push_str(&format!("\\u{:04x}", ch as u32));
+        } else {
+            escaped.push_str(&format!("\\u{{{:x}}}", ch as u32));
+        }
+    }
+    escaped
+}
+
+/// The code written so far, and the position at its end for source maps.
+#[derive(Default)]
+struct Writer {
+    code: String,
+    position: Position,
+    /// Whether to keep `</script` and `<!--` out of the code.
+    inline_script: bool,
+}
+
+impl Writer {
+    /// Adds a space if `text` would merge with the code before it.
+    fn separate(&mut self, prev: Option<&Token>, text: &str) {
+        // `a< /b/` and `a< !--b` can't end or comment out the script
+        let breaks_tag = self.inline_script
+            && self.code.ends_with('<')
+            && (text.starts_with('/') || text.starts_with('!'));
+        if Minifier::needs_space(&self.code, prev, text) || breaks_tag {
+            self.push(" ");
+        }
+    }
+
+    /// Moves the position past `text`, which may span lines in the case of
+    /// template literals and comments.
+    fn push(&mut self, text: &str) {
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            let crlf = ch == '\r' && chars.peek() == Some(&'\n');
+            if matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}') && !crlf {
+                self.position.line += 1;
+                self.position.column = 0;
+            } else {
+                self.position.column += ch.len_utf16() as u32;
             }
         }
-        code
+        self.code.push_str(text);
+    }
+
+    fn write(&mut self, prev: Option<&Token>, text: &str) {
+        self.separate(prev, text);
+        self.push(text);
+    }
+
+    fn write_comment(&mut self, comment: &Comment) {
+        let mut text = comment.to_string();
+        if self.inline_script {
+            text = escape_inline_script(&text, false);
+        }
+        self.write(None, &text);
+        if comment.t == CommentType::SingleLine {
+            self.push("\n");
+        }
     }
-    fn build_rule_string(&mut self, ch: char) -> String {
-        match self.lex.peek() {
-            Some(Token::Ident(_)) => {
-                if ch == '}' {
-                    return String::from(ch);
+}
+
+impl<'a> Minifier<'a> {
+    pub fn with_options(lex: Lexer, options: MinifyOptions) -> Minifier {
+        Minifier { lex, options }
+    }
+
+    /// Minifies the input. Fails if a name in `name_cache` can't be given
+    /// out again.
+    pub fn generate(&mut self) -> Result<Output, String> {
+        let mut tokens = TokenBuffer::new();
+        loop {
+            match self.lex.next_token() {
+                Token::EOF => {
+                    tokens.set_trailing(self.lex.take_comments());
+                    break;
+                }
+                tok => {
+                    let comments = self.lex.take_comments();
+                    tokens.push(tok, self.lex.token_start(), comments);
+                }
+            }
+        }
+        compress::compress(&mut tokens, &self.options);
+        let mut name_cache = self.options.name_cache.clone();
+        mangle::mangle_properties(&mut tokens, &self.options, name_cache.as_mut())?;
+        if self.options.mangle {
+            mangle::mangle(&mut tokens, &self.options, name_cache.as_mut())?;
+        }
+
+        let mut map = if self.options.source_map {
+            let mut map = SourceMap::new(self.options.file.clone());
+            let sources_content = self.options.sources_content;
+            match &self.options.input_source_map {
+                Some(input) => {
+                    for (name, content) in input.sources.iter().zip(&input.sources_content) {
+                        let content = content.as_deref().filter(|_| sources_content);
+                        map.add_source(name, content);
+                    }
+                }
+                None => {
+                    let name = self.options.source_file.as_deref().unwrap_or("input.js");
+                    let content = Some(self.lex.source()).filter(|_| sources_content);
+                    map.add_source(name, content);
+                }
+            }
+            Some(map)
+        } else {
+            None
+        };
+
+        let mut writer = Writer {
+            inline_script: self.options.inline_script(),
+            ..Writer::default()
+        };
+        let mut deferred: Vec<Comment> = Vec::new();
+        let mut extracted = Vec::new();
+        for (i, tok) in tokens.iter().enumerate() {
+            let mut annotation = None;
+            for comment in tokens.comments(i) {
+                match comment.annotation() {
+                    Some(text) if Self::is_call_start(&tokens, i) => annotation = Some(text),
+                    _ => self.sort_comment(comment, &mut deferred, &mut extracted),
+                }
+            }
+            if *tok == Token::Semicolon && Self::is_redundant_semicolon(&tokens, i) {
+                continue;
+            }
+            // comments that end in a line break can't go where one would end
+            // the statement
+            if !deferred.iter().any(Comment::breaks_line) || Self::allows_line_break(&tokens, i) {
+                for comment in deferred.drain(..) {
+                    writer.write_comment(&comment);
                 }
-                format!("{};", ch)
             }
-            Some(Token::Keyword(_)) => {
-                if ch == '}' {
-                    return format!("{}", ch);
+            let annotation = annotation.map(|text| format!("/*{}*/", text));
+            let mut text = tok.to_string();
+            if self.options.ascii_only {
+                text = match tok {
+                    Token::Ident(_) => escape_identifier(&text),
+                    Token::STRING(_) | Token::REGEX(_) => escape_literal(&text),
+                    _ => text,
+                };
+            }
+            if writer.inline_script {
+                text = match tok {
+                    Token::STRING(_) => escape_inline_script(&text, false),
+                    Token::REGEX(_) => escape_inline_script(&text, true),
+                    _ => text,
+                };
+            }
+            if let Some(max) = self.options.max_line_len {
+                let mut width: u32 = annotation
+                    .iter()
+                    .chain(Some(&text))
+                    .map(|text| utf16_len(text))
+                    .sum();
+                // `return` has to stay on the line of what it returns
+                if Self::forbids_line_break_after(tok) {
+                    width += tokens
+                        .get(i + 1)
+                        .map_or(0, |next| 1 + utf16_len(&next.to_string()));
+                }
+                if writer.position.column > 0
+                    && writer.position.column + 1 + width > max
+                    && Self::allows_line_break(&tokens, i)
+                {
+                    writer.push("\n");
                 }
-                format!("{};", ch)
             }
-            _ => String::from(ch),
+            if let Some(annotation) = annotation {
+                writer.write(None, &annotation);
+            }
+            let prev = if i > 0 { tokens.get(i - 1) } else { None };
+            writer.separate(prev, &text);
+            if let (Some(map), Some(original)) = (map.as_mut(), tokens.start(i)) {
+                let name = tokens.original_name(i);
+                self.add_mapping(map, tok, name, writer.position, original);
+            }
+            writer.push(&text);
+        }
+        for comment in tokens.trailing() {
+            self.sort_comment(comment, &mut deferred, &mut extracted);
         }
+        for comment in deferred {
+            writer.write_comment(&comment);
+        }
+
+        let mut code = writer.code;
+        let license = if extracted.is_empty() {
+            None
+        } else {
+            let name = match &self.options.file {
+                Some(file) => format!("{}.LICENSE.txt", file),
+                None => "LICENSE.txt".to_string(),
+            };
+            code.insert_str(
+                0,
+                &format!("/*! For license information please see {} */\n", name),
+            );
+            if let Some(map) = map.as_mut() {
+                for mapping in map.mappings.iter_mut() {
+                    mapping.generated.line += 1;
+                }
+            }
+            Some(extracted.join("\n\n") + "\n")
+        };
+        if let Some(url) = &self.options.source_map_url {
+            code.push_str("\n//# sourceMappingURL=");
+            code.push_str(url);
+        }
+        Ok(Output {
+            code,
+            map,
+            license,
+            name_cache,
+        })
+    }
+
+    /// Puts `comment` with the ones to keep or to extract, following the
+    /// `comments` and `extract_comments` options.
+    fn sort_comment(
+        &self,
+        comment: &Comment,
+        kept: &mut Vec<Comment>,
+        extracted: &mut Vec<String>,
+    ) {
+        if self.options.extract_comments && comment.is_license() {
+            extracted.push(comment.to_string());
+        } else if self.options.comments.keeps(comment) {
+            kept.push(comment.clone());
+        }
+    }
+
+    fn forbids_line_break_after(tok: &Token) -> bool {
+        matches!(
+            tok,
+            Token::Keyword(Keyword::RETURN)
+                | Token::Keyword(Keyword::THROW)
+                | Token::Keyword(Keyword::BREAK)
+                | Token::Keyword(Keyword::CONTINUE)
+                | Token::Keyword(Keyword::YIELD)
+                | Token::Keyword(Keyword::ASYNC)
+        )
+    }
+
+    /// Whether a line break can go right before the token at `i` without
+    /// ending the statement, which it would after `return` or before a
+    /// postfix `++`. Nor can one split the `?` and `.` of `?.`, which the
+    /// lexer reads as two tokens.
+    fn allows_line_break(tokens: &[Token], i: usize) -> bool {
+        let restricted = i > 0 && Self::forbids_line_break_after(&tokens[i - 1]);
+        let postfix = matches!(
+            tokens[i],
+            Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus)
+        ) && i > 0
+            && tokens[i - 1].ends_expression();
+        let optional_chain = tokens[i] == Token::Operator(Operator::Period)
+            && i > 0
+            && tokens[i - 1] == Token::Operator(Operator::Ternary);
+        !restricted && !postfix && !optional_chain && tokens[i] != Token::Operator(Operator::Arrow)
+    }
+
+    /// Maps the token written at `generated` back to where it was read from,
+    /// going through the input source map if there is one. Renamed
+    /// identifiers pass the name they were read with as `original_name`.
+    fn add_mapping(
+        &self,
+        map: &mut SourceMap,
+        tok: &Token,
+        original_name: Option<&str>,
+        generated: Position,
+        read: Position,
+    ) {
+        let mut mapping = Mapping {
+            generated,
+            source: 0,
+            original: read,
+            name: None,
+        };
+        let mut name = match tok {
+            Token::Ident(name) => Some(original_name.unwrap_or(name)),
+            _ => None,
+        };
+        if let Some(input) = &self.options.input_source_map {
+            let found = match input.find(read) {
+                Some(found) => found,
+                // the code came from nowhere in the original sources
+                None => return,
+            };
+            mapping.source = found.source;
+            mapping.original = found.original;
+            // a name only belongs to the token its mapping starts at
+            name = match found.name {
+                Some(index) if found.generated == read => {
+                    input.names.get(index as usize).map(String::as_str)
+                }
+                _ => None,
+            };
+        }
+        mapping.name = name.map(|name| map.add_name(name));
+        map.add_mapping(mapping);
+    }
+
+    /// Whether the expression starting at `i` is a call or `new`, which is
+    /// what `/*#__PURE__*/` annotations apply to.
+    fn is_call_start(tokens: &[Token], i: usize) -> bool {
+        if tokens[i] == Token::Keyword(Keyword::NEW) {
+            return true;
+        }
+        let end = util::operand_end(tokens, i, precedence::MEMBER);
+        end > i + 1
+            && tokens[end - 1] == Token::RightParen
+            && util::matching_open(tokens, end - 1).is_some_and(|open| open > i)
+    }
+
+    /// A semicolon can be left out before a `}` or the end of the input,
+    /// unless it is an empty statement body like in `if(a);`.
+    fn is_redundant_semicolon(tokens: &[Token], i: usize) -> bool {
+        matches!(tokens.get(i + 1), None | Some(Token::RightBracket))
+            && !util::is_body_position(tokens, i)
+    }
+
+    fn is_word_char(ch: char) -> bool {
+        ch.is_alphanumeric() || ch == '_' || ch == '$' || ch == '\\' || !ch.is_ascii()
+    }
+
+    /// Whether `text` would merge with the end of `code` if written directly
+    /// after it, like two words or `a - -b`.
+    fn needs_space(code: &str, prev: Option<&Token>, text: &str) -> bool {
+        let (last, first) = match (code.chars().next_back(), text.chars().next()) {
+            (Some(last), Some(first)) => (last, first),
+            _ => return false,
+        };
+        if Self::is_word_char(last) && Self::is_word_char(first) {
+            return true;
+        }
+        match (last, first) {
+            ('+', '+') | ('-', '-') | ('/', '/') | ('/', '*') | ('<', '!') => true,
+            // `1.toString()` would read the period as a decimal point
+            (_, '.') => match prev {
+                Some(Token::NUMBER(number)) => number.chars().all(|ch| ch.is_ascii_digit()),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Minifies `input` with the options `configure` sets, for the tests of the
+/// passes.
+#[cfg(test)]
+pub fn minify_with(input: &str, configure: impl FnOnce(&mut MinifyOptions)) -> String {
+    let mut options = MinifyOptions::default();
+    configure(&mut options);
+    Minifier::with_options(Lexer::new(input), options)
+        .generate()
+        .unwrap()
+        .code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_identifier, escape_inline_script, escape_literal, minify_with, Minifier};
+    use crate::lexer::Lexer;
+    use crate::options::{Comments, MinifyOptions};
+
+    #[test]
+    fn keeps_annotations_on_calls() {
+        let minify = |input| minify_with(input, |_| {});
+        assert_eq!(minify("x = /* #__PURE__ */ f(a)"), "x=/*#__PURE__*/f(a)");
+        assert_eq!(minify("x = /*@__PURE__*/ new A"), "x=/*@__PURE__*/new A");
+        assert_eq!(minify("/*#__NOINLINE__*/ a.b()"), "/*#__NOINLINE__*/a.b()");
+        assert_eq!(minify("x = /* #__PURE__ */ a"), "x=a");
+        assert_eq!(minify("x = /* a */ f()"), "x=f()");
+    }
+
+    #[test]
+    fn escapes_literals_as_utf16() {
+        assert_eq!(escape_literal("'é'"), "'\\u00e9'");
+        assert_eq!(escape_literal("'😀'"), "'\\ud83d\\ude00'");
+        assert_eq!(escape_literal("'\\é\\n'"), "'\\u00e9\\n'");
+        assert_eq!(escape_literal("'a\\\u{2028}b'"), "'ab'");
+        assert_eq!(escape_literal("/[é]/g"), "/[\\u00e9]/g");
+    }
+
+    #[test]
+    fn escapes_identifiers() {
+        assert_eq!(escape_identifier("café"), "caf\\u00e9");
+        assert_eq!(escape_identifier("a𐊧"), "a\\u{102a7}");
+    }
+
+    #[test]
+    fn keeps_output_ascii_only() {
+        let ascii_only = |input| minify_with(input, |o| o.ascii_only = true);
+        assert_eq!(
+            ascii_only("var ü = '😀' + /ä/"),
+            "var \\u00fc='\\ud83d\\ude00'+/\\u00e4/"
+        );
+    }
+
+    #[test]
+    fn escapes_script_end_tags_and_comment_openers() {
+        assert_eq!(escape_inline_script("'</script>'", false), "'<\\/script>'");
+        assert_eq!(escape_inline_script("'</SCRIPT>'", false), "'<\\/SCRIPT>'");
+        assert_eq!(escape_inline_script("'</ScRiPt'", false), "'<\\/ScRiPt'");
+        assert_eq!(escape_inline_script("'<!--'", false), "'<\\!--'");
+        assert_eq!(escape_inline_script("'</div><b'", false), "'</div><b'");
+        assert_eq!(escape_inline_script("'</scrip'", false), "'</scrip'");
+    }
+
+    #[test]
+    fn escapes_comment_openers_in_regexes() {
+        assert_eq!(escape_inline_script("/<!--/", true), "/\\x3c!--/");
+        assert_eq!(escape_inline_script("/\\<!--/u", true), "/\\x3c!--/u");
+        assert_eq!(escape_inline_script("/\\\\<!--/", true), "/\\\\\\x3c!--/");
+        assert_eq!(
+            escape_inline_script("/<\\/script>/i", true),
+            "/<\\/script>/i"
+        );
+        assert_eq!(escape_inline_script("/a<b/", true), "/a<b/");
+    }
+
+    #[test]
+    fn escapes_inline_scripts() {
+        let html_inline = |input| minify_with(input, |o| o.html_inline = true);
+        assert_eq!(
+            html_inline("x = '</Script>' + /<!--/"),
+            "x='<\\/Script>'+/\\x3c!--/"
+        );
+    }
+
+    #[test]
+    fn wraps_long_lines() {
+        let wrap = |input| minify_with(input, |o| o.max_line_len = Some(10));
+        assert_eq!(
+            wrap("var abc = 1, def = 2, ghi = 3"),
+            "var abc=1\n,def=2,\nghi=3"
+        );
+        assert_eq!(wrap("aaaaaaaaaaaaaaa = 1"), "aaaaaaaaaaaaaaa\n=1");
+    }
+
+    #[test]
+    fn wraps_only_where_no_semicolon_is_inserted() {
+        let wrap = |input| minify_with(input, |o| o.max_line_len = Some(5));
+        assert_eq!(
+            wrap("function f() { return abc }"),
+            "function\nf(){\nreturn abc\n}"
+        );
+        assert_eq!(wrap("xxxx = yyyy++"), "xxxx\n=\nyyyy++");
+        assert_eq!(wrap("xxxx = (a) => a"), "xxxx\n=(a)=>\na");
+    }
+
+    #[test]
+    fn keeps_optional_chains_together() {
+        let wrap = |input| minify_with(input, |o| o.max_line_len = Some(5));
+        assert_eq!(wrap("x = y ?. z"), "x=y?.\nz");
+        assert_eq!(wrap("x = y ?. [z]"), "x=y?.\n[z]");
+        assert_eq!(wrap("x = y ? .5 : z"), "x=y?\n.5:z");
+    }
+
+    #[test]
+    fn keeps_license_comments() {
+        let license = |input| minify_with(input, |o| o.comments = Comments::License);
+        assert_eq!(
+            license("/*! (c) A */\nvar a = 1; // b"),
+            "/*! (c) A */var a=1"
+        );
+        assert_eq!(license("/* @license MIT */ a()"), "/* @license MIT */a()");
+        assert_eq!(license("/* b */ a()"), "a()");
+        let all = |input| minify_with(input, |o| o.comments = Comments::All);
+        assert_eq!(all("a() // b\nc()"), "a();// b\nc()");
+    }
+
+    #[test]
+    fn extracts_license_comments() {
+        let options = MinifyOptions {
+            comments: Comments::License,
+            extract_comments: true,
+            file: Some("out.js".to_string()),
+            ..MinifyOptions::default()
+        };
+        let output = Minifier::with_options(Lexer::new("/*! (c) A */\na()"), options)
+            .generate()
+            .unwrap();
+        assert_eq!(
+            output.code,
+            "/*! For license information please see out.js.LICENSE.txt */\na()"
+        );
+        assert_eq!(output.license.unwrap(), "/*! (c) A */\n");
     }
 }
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+/// The names given out by `mangle` and `mangle_props`, kept in a JSON file
+/// so that code minified in separate runs, like lazy chunks and workers,
+/// agrees on them.
+#[derive(Clone, Debug, Default)]
+pub struct NameCache {
+    /// Top-level bindings, renamed with `toplevel`.
+    pub vars: BTreeMap<String, String>,
+    pub props: BTreeMap<String, String>,
+}
+
+impl NameCache {
+    /// Reads a name cache from its JSON, like
+    /// `{"vars":{"foo":"a"},"props":{"_count":"b"}}`.
+    pub fn parse(json: &str) -> Result<NameCache, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|err| format!("invalid name cache: {}", err))?;
+        let names = |key: &str| -> Result<BTreeMap<String, String>, String> {
+            let mut names = BTreeMap::new();
+            let entries = match &value[key] {
+                Value::Null => return Ok(names),
+                Value::Object(entries) => entries,
+                _ => return Err(format!("name cache's `{}` is not an object", key)),
+            };
+            // which name each mangled name was given to
+            let mut given_to: BTreeMap<&str, &str> = BTreeMap::new();
+            for (name, mangled) in entries {
+                let mangled = mangled
+                    .as_str()
+                    .ok_or_else(|| format!("name cache has no name for `{}`", name))?;
+                if let Some(other) = given_to.insert(mangled, name) {
+                    return Err(format!(
+                        "name cache gives `{}` to both `{}` and `{}`",
+                        mangled, other, name
+                    ));
+                }
+                names.insert(name.clone(), mangled.to_string());
+            }
+            Ok(names)
+        };
+        Ok(NameCache {
+            vars: names("vars")?,
+            props: names("props")?,
+        })
+    }
+
+    /// Loads the name cache at `path`. A file that doesn't exist yet is an
+    /// empty cache, for the first run.
+    pub fn load(path: &str) -> Result<NameCache, String> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => NameCache::parse(&json),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(NameCache::default()),
+            Err(err) => Err(format!("couldn't read name cache `{}`: {}", path, err)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json())
+            .map_err(|err| format!("couldn't write name cache `{}`: {}", path, err))
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "vars": self.vars,
+            "props": self.props,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::NameCache;
+    use crate::lexer::Lexer;
+    use crate::minify::Minifier;
+    use crate::options::MinifyOptions;
+
+    #[test]
+    fn parses_and_writes_json() {
+        let cache = NameCache::parse(r#"{"vars":{"foo":"a"},"props":{"_count":"b"}}"#).unwrap();
+        assert_eq!(
+            cache.to_json(),
+            r#"{"props":{"_count":"b"},"vars":{"foo":"a"}}"#
+        );
+        let empty = NameCache::parse("{}").unwrap();
+        assert_eq!(empty.to_json(), r#"{"props":{},"vars":{}}"#);
+    }
+
+    #[test]
+    fn rejects_malformed_caches() {
+        assert_eq!(
+            NameCache::parse(r#"{"vars":[]}"#).err(),
+            Some("name cache's `vars` is not an object".to_string())
+        );
+        assert_eq!(
+            NameCache::parse(r#"{"props":{"a":1}}"#).err(),
+            Some("name cache has no name for `a`".to_string())
+        );
+    }
+
+    #[test]
+    fn reuses_and_adds_names() {
+        let options = MinifyOptions {
+            mangle: true,
+            toplevel: true,
+            mangle_props: Some(Regex::new("^_").unwrap()),
+            name_cache: Some(
+                NameCache::parse(r#"{"vars":{"foo":"q"},"props":{"_count":"z"}}"#).unwrap(),
+            ),
+            ..MinifyOptions::default()
+        };
+        let input = "var foo = 1, bar = 2; x._count = x._other + foo + bar";
+        let output = Minifier::with_options(Lexer::new(input), options)
+            .generate()
+            .unwrap();
+        assert_eq!(output.code, "var q=1,a=2;x.z=x.a+q+a");
+        assert_eq!(
+            output.name_cache.unwrap().to_json(),
+            r#"{"props":{"_count":"z","_other":"a"},"vars":{"bar":"a","foo":"q"}}"#
+        );
+    }
+
+    #[test]
+    fn rejects_names_given_out_twice() {
+        assert_eq!(
+            NameCache::parse(r#"{"props":{"_y":"a","_z":"a"}}"#).err(),
+            Some("name cache gives `a` to both `_y` and `_z`".to_string())
+        );
+        assert!(NameCache::parse(r#"{"vars":{"a":"b"},"props":{"_a":"b"}}"#).is_ok());
+    }
+
+    #[test]
+    fn fails_rather_than_renaming_cached_names() {
+        let minify = |input, cache| {
+            let options = MinifyOptions {
+                mangle: true,
+                toplevel: true,
+                mangle_props: Some(Regex::new("^_").unwrap()),
+                name_cache: Some(NameCache::parse(cache).unwrap()),
+                ..MinifyOptions::default()
+            };
+            Minifier::with_options(Lexer::new(input), options)
+                .generate()
+                .map(|output| output.code)
+        };
+        assert_eq!(
+            minify("o._x = 1; o._y = 2; o.a", r#"{"props":{"_y":"a"}}"#),
+            Err("name cache renames property `_y` to `a`, which the code already uses".to_string())
+        );
+        assert_eq!(
+            minify("var foo = a", r#"{"vars":{"foo":"a"}}"#),
+            Err("name cache renames `foo` to `a`, which the code already uses".to_string())
+        );
+        assert_eq!(
+            minify("o._x = 1; o._y = 2", r#"{"props":{"_y":"a"}}"#),
+            Ok("o.b=1;o.a=2".to_string())
+        );
+    }
+}
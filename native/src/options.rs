@@ -0,0 +1,136 @@
+use regex::Regex;
+
+use super::name_cache::NameCache;
+use super::source_map::SourceMap;
+use super::token::Comment;
+
+/// Which comments are kept in the output.
+#[derive(Clone, Debug, Default)]
+pub enum Comments {
+    #[default]
+    None,
+    /// `/*!` comments and ones with `@license` or `@preserve`.
+    License,
+    All,
+    /// Comments whose text matches the regex.
+    Matching(Regex),
+}
+
+impl Comments {
+    pub fn keeps(&self, comment: &Comment) -> bool {
+        match self {
+            Comments::None => false,
+            Comments::License => comment.is_license(),
+            Comments::All => true,
+            Comments::Matching(regex) => regex.is_match(&comment.text),
+        }
+    }
+}
+
+/// Settings that control which transforms the `Minifier` applies on top of
+/// whitespace and comment removal.
+#[derive(Clone, Debug, Default)]
+pub struct MinifyOptions {
+    /// Remove `debugger` statements.
+    pub drop_debugger: bool,
+    /// Functions whose calls are dropped when used as statements, written as
+    /// they appear in the source (`console.log`, `assert`).
+    pub pure_funcs: Vec<String>,
+    /// Identifiers or dotted chains like `process.env.NODE_ENV`, each paired
+    /// with the source of the expression that replaces it.
+    pub define: Vec<(String, String)>,
+    /// Collapse `if` statements, conditionals and logical expressions with a
+    /// constant condition to the branch that runs, and remove statements that
+    /// can't be reached.
+    pub dead_code: bool,
+    /// Replace operations on literals with their result, like `1<<4` with
+    /// `16`.
+    pub fold_constants: bool,
+    /// Remove repeated directives, and `"use strict"` where the code is
+    /// already strict.
+    pub directives: bool,
+    /// The input is an ES module, so it is strict without `"use strict"`.
+    pub module: bool,
+    /// Turn `if` statements whose branches are expressions into `&&`, `||`
+    /// and `?:`.
+    pub conditionals: bool,
+    /// Join consecutive declarations of the same kind, and assignments right
+    /// after a declaration, into one declaration.
+    pub join_vars: bool,
+    /// Remove the braces around single statement bodies of `if`, `else` and
+    /// loops.
+    pub drop_braces: bool,
+    /// Remove empty statements and branches, and trailing commas.
+    pub redundant_punctuation: bool,
+    /// Shorten arrow functions, dropping the parentheses around a single
+    /// parameter and turning `{return x}` bodies into `x`.
+    pub arrows: bool,
+    /// Use `==` and `!=` where both sides have the same type, and compare
+    /// `typeof` to `"u"` instead of `"undefined"`.
+    pub comparisons: bool,
+    /// Turn `{a:a}` into `{a}` and function valued properties into methods,
+    /// when `ecma` is 2015 or later.
+    pub shorthand: bool,
+    /// Remove parentheses that don't change how an expression is evaluated.
+    pub drop_parens: bool,
+    /// Rename variables, parameters, functions and classes that are local to
+    /// a function or block to short names.
+    pub mangle: bool,
+    /// Let `mangle` rename top-level bindings too, for modules and scripts
+    /// whose globals aren't used by other scripts. Exports keep their names.
+    pub toplevel: bool,
+    /// Rename the properties whose names match the regex, like `^_`.
+    pub mangle_props: Option<Regex>,
+    /// Properties that keep their names even if they match `mangle_props`,
+    /// on top of the built-in ones.
+    pub reserved_props: Vec<String>,
+    /// Let `mangle_props` rename quoted keys and `a["b"]` too. Otherwise a
+    /// name that is ever written in quotes is kept everywhere.
+    pub mangle_quoted: bool,
+    /// Names given out by earlier runs, which the renamed properties and
+    /// top-level bindings keep. The ones this run gives out are added to it
+    /// in `Output::name_cache`.
+    pub name_cache: Option<NameCache>,
+    /// The ECMAScript version the output may use, like `2015`. Defaults to
+    /// `5`, which keeps transforms from introducing newer syntax.
+    pub ecma: Option<u32>,
+    /// Build a source map for the output.
+    pub source_map: bool,
+    /// Name of the input in the source map's `sources`.
+    pub source_file: Option<String>,
+    /// Name of the output, written to the source map's `file`.
+    pub file: Option<String>,
+    /// Embed the input in the source map's `sourcesContent`.
+    pub sources_content: bool,
+    /// Where the source map will be served from, appended to the output as a
+    /// `//# sourceMappingURL=` comment.
+    pub source_map_url: Option<String>,
+    /// The source map the input was generated with, like TypeScript's. The
+    /// output's source map then points into its sources instead.
+    pub input_source_map: Option<SourceMap>,
+    /// Which comments to keep.
+    pub comments: Comments,
+    /// Move license comments out of the code, into `Output::license`, and
+    /// leave a banner pointing at `<file>.LICENSE.txt` in their place.
+    pub extract_comments: bool,
+    /// Break the output into lines of at most this many columns, where a
+    /// line break doesn't change what the code means.
+    pub max_line_len: Option<u32>,
+    /// Escape non-ASCII characters, for scripts served without a charset.
+    pub ascii_only: bool,
+    /// The output is going to be inlined into an HTML page.
+    pub html_inline: bool,
+    /// Escape `</script` and `<!--` in literals and comments. Defaults to
+    /// `html_inline`.
+    pub inline_script: Option<bool>,
+}
+
+impl MinifyOptions {
+    pub fn ecma(&self) -> u32 {
+        self.ecma.unwrap_or(5)
+    }
+
+    pub fn inline_script(&self) -> bool {
+        self.inline_script.unwrap_or(self.html_inline)
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use super::buffer::Position;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Ties a position in the generated code to one in a source file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub generated: Position,
+    pub source: u32,
+    pub original: Position,
+    pub name: Option<u32>,
+}
+
+/// A Source Map v3, built up as the minified code is written.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    pub file: Option<String>,
+    pub sources: Vec<String>,
+    pub sources_content: Vec<Option<String>>,
+    pub names: Vec<String>,
+    /// Sorted by their generated position.
+    pub mappings: Vec<Mapping>,
+    name_indices: HashMap<String, u32>,
+}
+
+impl SourceMap {
+    pub fn new(file: Option<String>) -> SourceMap {
+        SourceMap {
+            file,
+            ..SourceMap::default()
+        }
+    }
+
+    /// Reads a source map from its JSON, with `sourceRoot` resolved into the
+    /// names in `sources`.
+    pub fn parse(json: &str) -> Result<SourceMap, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|err| format!("invalid source map: {}", err))?;
+        if value["version"] != 3 {
+            return Err("only version 3 source maps are supported".to_string());
+        }
+        let strings = |key: &str| -> Vec<Option<String>> {
+            match value[key].as_array() {
+                Some(items) => items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect(),
+                None => Vec::new(),
+            }
+        };
+        let root = value["sourceRoot"].as_str().unwrap_or("");
+        let mut map = SourceMap::new(value["file"].as_str().map(str::to_string));
+        let contents = strings("sourcesContent");
+        for (i, source) in strings("sources").into_iter().enumerate() {
+            let source = source.unwrap_or_default();
+            let name = if root.is_empty() || root.ends_with('/') {
+                format!("{}{}", root, source)
+            } else {
+                format!("{}/{}", root, source)
+            };
+            map.add_source(&name, contents.get(i).cloned().flatten().as_deref());
+        }
+        for name in strings("names") {
+            map.names.push(name.unwrap_or_default());
+        }
+        let mappings = value["mappings"]
+            .as_str()
+            .ok_or_else(|| "source map has no mappings".to_string())?;
+        map.mappings = decode_mappings(mappings)?;
+        Ok(map)
+    }
+
+    /// The mapping that covers `position` in the generated code.
+    pub fn find(&self, position: Position) -> Option<&Mapping> {
+        let after = self.mappings.partition_point(|mapping| {
+            let generated = mapping.generated;
+            (generated.line, generated.column) <= (position.line, position.column)
+        });
+        let mapping = self.mappings.get(after.checked_sub(1)?)?;
+        if mapping.generated.line == position.line {
+            Some(mapping)
+        } else {
+            None
+        }
+    }
+
+    /// Adds a source file, returning its index in `sources`.
+    pub fn add_source(&mut self, name: &str, content: Option<&str>) -> u32 {
+        self.sources.push(name.to_string());
+        self.sources_content.push(content.map(str::to_string));
+        self.sources.len() as u32 - 1
+    }
+
+    /// Returns the index of `name` in `names`, adding it if it is new.
+    pub fn add_name(&mut self, name: &str) -> u32 {
+        if let Some(&index) = self.name_indices.get(name) {
+            return index;
+        }
+        let index = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.name_indices.insert(name.to_string(), index);
+        index
+    }
+
+    /// Adds a mapping, which has to come after the ones added before it in
+    /// the generated code.
+    pub fn add_mapping(&mut self, mapping: Mapping) {
+        self.mappings.push(mapping);
+    }
+
+    /// The `mappings` field, with each segment stored relative to the one
+    /// before it as base64 VLQs.
+    pub fn encode_mappings(&self) -> String {
+        let mut encoded = String::new();
+        let mut line = 0;
+        let mut column = 0;
+        let mut source = 0;
+        let mut original = Position { line: 0, column: 0 };
+        let mut name = 0;
+        for (i, mapping) in self.mappings.iter().enumerate() {
+            if mapping.generated.line != line {
+                while line < mapping.generated.line {
+                    encoded.push(';');
+                    line += 1;
+                }
+                column = 0;
+            } else if i > 0 {
+                encoded.push(',');
+            }
+            encode_vlq(&mut encoded, mapping.generated.column as i64 - column);
+            encode_vlq(&mut encoded, mapping.source as i64 - source);
+            encode_vlq(
+                &mut encoded,
+                mapping.original.line as i64 - original.line as i64,
+            );
+            encode_vlq(
+                &mut encoded,
+                mapping.original.column as i64 - original.column as i64,
+            );
+            if let Some(index) = mapping.name {
+                encode_vlq(&mut encoded, index as i64 - name);
+                name = index as i64;
+            }
+            column = mapping.generated.column as i64;
+            source = mapping.source as i64;
+            original = mapping.original;
+        }
+        encoded
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut map = json!({
+            "version": 3,
+            "sources": self.sources,
+            "names": self.names,
+            "mappings": self.encode_mappings(),
+        });
+        if let Some(file) = &self.file {
+            map["file"] = json!(file);
+        }
+        if self.sources_content.iter().any(Option::is_some) {
+            map["sourcesContent"] = json!(self.sources_content);
+        }
+        map.to_string()
+    }
+}
+
+/// Reads the `mappings` field back into mappings. Segments that only have a
+/// generated column map to no source, so they are left out.
+fn decode_mappings(encoded: &str) -> Result<Vec<Mapping>, String> {
+    let mut mappings = Vec::new();
+    let mut fields = [0i64; 5];
+    for (line, segments) in encoded.split(';').enumerate() {
+        fields[0] = 0;
+        for segment in segments.split(',').filter(|segment| !segment.is_empty()) {
+            let mut chars = segment.chars().peekable();
+            let mut count = 0;
+            while chars.peek().is_some() {
+                if count == fields.len() {
+                    return Err(format!("invalid mapping segment `{}`", segment));
+                }
+                fields[count] += decode_vlq(&mut chars)
+                    .ok_or_else(|| format!("invalid mapping segment `{}`", segment))?;
+                count += 1;
+            }
+            if count < 4 {
+                continue;
+            }
+            if fields[..4].iter().any(|&field| field < 0) || (count == 5 && fields[4] < 0) {
+                return Err(format!("invalid mapping segment `{}`", segment));
+            }
+            mappings.push(Mapping {
+                generated: Position {
+                    line: line as u32,
+                    column: fields[0] as u32,
+                },
+                source: fields[1] as u32,
+                original: Position {
+                    line: fields[2] as u32,
+                    column: fields[3] as u32,
+                },
+                name: if count == 5 {
+                    Some(fields[4] as u32)
+                } else {
+                    None
+                },
+            });
+        }
+    }
+    mappings.sort_by_key(|mapping| (mapping.generated.line, mapping.generated.column));
+    Ok(mappings)
+}
+
+fn decode_vlq(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<i64> {
+    let mut vlq: i64 = 0;
+    let mut shift = 0;
+    loop {
+        let ch = chars.next()?;
+        let digit = BASE64.iter().position(|&b| b as char == ch)? as i64;
+        vlq |= (digit & 31) << shift;
+        if digit & 32 == 0 {
+            break;
+        }
+        shift += 5;
+        if shift > 60 {
+            return None;
+        }
+    }
+    Some(if vlq & 1 == 1 { -(vlq >> 1) } else { vlq >> 1 })
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for ch in encoded.trim_end_matches('=').chars() {
+        let digit = BASE64.iter().position(|&b| b as char == ch)? as u32;
+        buffer = (buffer << 6) | digit;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Loads the source map that `input` was generated with. `spec` is either
+/// `inline`, to read it from a `//# sourceMappingURL=data:` comment at the end
+/// of `input`, or the path of a map file.
+pub fn load_input_map(input: &str, spec: &str) -> Result<SourceMap, String> {
+    if spec != "inline" {
+        let json = std::fs::read_to_string(spec)
+            .map_err(|err| format!("couldn't read source map `{}`: {}", spec, err))?;
+        return SourceMap::parse(&json);
+    }
+    let url =
+        source_mapping_url(input).ok_or_else(|| "input has no inline source map".to_string())?;
+    let (header, data) = match url.strip_prefix("data:") {
+        Some(url) => url
+            .split_once(',')
+            .ok_or_else(|| "invalid source map data URL".to_string())?,
+        None => return Err("input's source map is not inline".to_string()),
+    };
+    let json = if header.ends_with(";base64") {
+        let bytes =
+            decode_base64(data).ok_or_else(|| "invalid base64 in source map".to_string())?;
+        String::from_utf8(bytes).map_err(|_| "source map is not UTF-8".to_string())?
+    } else {
+        percent_decode(data)
+    };
+    SourceMap::parse(&json)
+}
+
+/// The URL in the `//# sourceMappingURL=` or `/*# sourceMappingURL= */`
+/// comment that ends `input`, if it ends with one.
+fn source_mapping_url(input: &str) -> Option<&str> {
+    let input = input.trim_end();
+    let text = match input.strip_suffix("*/") {
+        Some(rest) => {
+            let text = &rest[rest.rfind("/*")? + 2..];
+            if text.contains("*/") {
+                return None;
+            }
+            text
+        }
+        None => {
+            let line = input.rsplit(['\n', '\r', '\u{2028}', '\u{2029}']).next()?;
+            line.trim_start().strip_prefix("//")?
+        }
+    };
+    text.strip_prefix(['#', '@'])?
+        .trim_start()
+        .strip_prefix("sourceMappingURL=")?
+        .split_whitespace()
+        .next()
+}
+
+fn percent_decode(encoded: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 31;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_base64, decode_mappings, encode_vlq, load_input_map, percent_decode,
+        source_mapping_url, Mapping, SourceMap,
+    };
+    use crate::buffer::Position;
+    use crate::lexer::Lexer;
+    use crate::minify::Minifier;
+    use crate::options::MinifyOptions;
+
+    fn vlq(value: i64) -> String {
+        let mut out = String::new();
+        encode_vlq(&mut out, value);
+        out
+    }
+
+    fn mapping(generated: (u32, u32), original: (u32, u32), name: Option<u32>) -> Mapping {
+        Mapping {
+            generated: Position {
+                line: generated.0,
+                column: generated.1,
+            },
+            source: 0,
+            original: Position {
+                line: original.0,
+                column: original.1,
+            },
+            name,
+        }
+    }
+
+    #[test]
+    fn encodes_vlqs() {
+        assert_eq!(vlq(0), "A");
+        assert_eq!(vlq(1), "C");
+        assert_eq!(vlq(-1), "D");
+        assert_eq!(vlq(15), "e");
+        assert_eq!(vlq(16), "gB");
+        assert_eq!(vlq(-16), "hB");
+        assert_eq!(vlq(123), "2H");
+    }
+
+    #[test]
+    fn encodes_mappings_relative_to_the_last() {
+        let mut map = SourceMap::new(None);
+        map.add_source("a.js", None);
+        map.add_name("foo");
+        map.add_mapping(mapping((0, 0), (0, 0), None));
+        map.add_mapping(mapping((0, 4), (0, 5), Some(0)));
+        map.add_mapping(mapping((2, 1), (3, 2), None));
+        assert_eq!(map.encode_mappings(), "AAAA,IAAKA;;CAGH");
+    }
+
+    #[test]
+    fn maps_minified_tokens_to_their_source() {
+        let options = MinifyOptions {
+            source_map: true,
+            ..MinifyOptions::default()
+        };
+        let input = "var a = 1;\n\n  foo(a)";
+        let output = Minifier::with_options(Lexer::new(input), options)
+            .generate()
+            .unwrap();
+        let column = output.code.find("foo").unwrap() as u32;
+        let map = output.map.unwrap();
+        let foo = map
+            .mappings
+            .iter()
+            .find(|mapping| mapping.generated.column == column)
+            .unwrap();
+        assert_eq!(foo.original, Position { line: 2, column: 2 });
+        assert_eq!(map.names[foo.name.unwrap() as usize], "foo");
+    }
+
+    #[test]
+    fn decodes_mappings() {
+        assert_eq!(
+            decode_mappings("AAAA,IAAKA").unwrap(),
+            vec![
+                mapping((0, 0), (0, 0), None),
+                mapping((0, 4), (0, 5), Some(0))
+            ]
+        );
+        assert!(decode_mappings("AAAA,I!").is_err());
+        assert!(decode_mappings("AAAAAA").is_err());
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let mut map = SourceMap::new(None);
+        map.add_source("a.js", None);
+        map.add_mapping(mapping((0, 3), (4, 100), None));
+        map.add_mapping(mapping((0, 900), (1, 0), Some(2)));
+        map.add_mapping(mapping((5, 0), (0, 7), Some(0)));
+        assert_eq!(
+            decode_mappings(&map.encode_mappings()).unwrap(),
+            map.mappings
+        );
+    }
+
+    #[test]
+    fn decodes_data_urls() {
+        assert_eq!(
+            decode_base64("eyJhIjoxfQ==").unwrap(),
+            b"{\"a\":1}".to_vec()
+        );
+        assert_eq!(decode_base64("e!"), None);
+        assert_eq!(percent_decode("%7B%22a%22%3A1%7D"), "{\"a\":1}");
+        assert_eq!(percent_decode("100%25 %zz"), "100% %zz");
+    }
+
+    #[test]
+    fn resolves_sources_against_the_source_root() {
+        let json =
+            r#"{"version":3,"sourceRoot":"src","sources":["a.js"],"names":[],"mappings":""}"#;
+        assert_eq!(SourceMap::parse(json).unwrap().sources, vec!["src/a.js"]);
+        let json = r#"{"version":3,"sourceRoot":"src/","sources":["a.js"],"mappings":""}"#;
+        assert_eq!(SourceMap::parse(json).unwrap().sources, vec!["src/a.js"]);
+        let json = r#"{"version":2,"sources":[],"mappings":""}"#;
+        assert!(SourceMap::parse(json).is_err());
+    }
+
+    #[test]
+    fn finds_the_mapping_a_position_falls_in() {
+        let mut map = SourceMap::new(None);
+        map.add_mapping(mapping((0, 2), (0, 0), None));
+        map.add_mapping(mapping((0, 8), (3, 0), None));
+        map.add_mapping(mapping((1, 4), (5, 0), None));
+        let find = |line, column| {
+            map.find(Position { line, column })
+                .map(|mapping| mapping.original.line)
+        };
+        assert_eq!(find(0, 0), None);
+        assert_eq!(find(0, 2), Some(0));
+        assert_eq!(find(0, 7), Some(0));
+        assert_eq!(find(0, 50), Some(3));
+        assert_eq!(find(1, 3), None);
+        assert_eq!(find(1, 4), Some(5));
+    }
+
+    #[test]
+    fn loads_inline_maps() {
+        let json = r#"{"version":3,"sources":["a.ts"],"names":[],"mappings":"AAAA"}"#;
+        let input = format!(
+            "a()\n//# sourceMappingURL=data:application/json;base64,{}",
+            "eyJ2ZXJzaW9uIjozLCJzb3VyY2VzIjpbImEudHMiXSwibmFtZXMiOltdLCJtYXBwaW5ncyI6IkFBQUEifQ=="
+        );
+        assert_eq!(
+            load_input_map(&input, "inline").unwrap().sources,
+            vec!["a.ts"]
+        );
+        let input = format!(
+            "a()\n//# sourceMappingURL=data:application/json,{}",
+            json.replace('"', "%22")
+        );
+        assert_eq!(
+            load_input_map(&input, "inline").unwrap().sources,
+            vec!["a.ts"]
+        );
+        assert!(load_input_map("a()", "inline").is_err());
+        assert!(load_input_map("a()\n//# sourceMappingURL=a.js.map", "inline").is_err());
+    }
+
+    #[test]
+    fn reads_the_url_from_the_trailing_comment_only() {
+        assert_eq!(
+            source_mapping_url("a()\n//# sourceMappingURL=a.map\n"),
+            Some("a.map")
+        );
+        assert_eq!(
+            source_mapping_url("a()\n/*# sourceMappingURL=a.map */"),
+            Some("a.map")
+        );
+        assert_eq!(
+            source_mapping_url("a()\n//@ sourceMappingURL=a.map"),
+            Some("a.map")
+        );
+        assert_eq!(source_mapping_url("x = '//# sourceMappingURL=a.map'"), None);
+        assert_eq!(source_mapping_url("//# sourceMappingURL=a.map\na()"), None);
+        assert_eq!(
+            source_mapping_url("x = '/*# sourceMappingURL=a.map */'"),
+            None
+        );
+        assert_eq!(
+            source_mapping_url("/*# sourceMappingURL=a.map */ a() /* b */"),
+            None
+        );
+    }
+
+    #[test]
+    fn composes_with_the_input_map() {
+        // `foo` on the second line came from `bar` at line 10, column 4 of
+        // orig.ts
+        let json = r#"{"version":3,"sources":["orig.ts"],"names":["bar"],"mappings":"AAAA;AAUIA"}"#;
+        let options = MinifyOptions {
+            source_map: true,
+            input_source_map: Some(SourceMap::parse(json).unwrap()),
+            ..MinifyOptions::default()
+        };
+        let output = Minifier::with_options(Lexer::new("var a = 1;\nfoo(a)"), options)
+            .generate()
+            .unwrap();
+        let column = output.code.find("foo").unwrap() as u32;
+        let map = output.map.unwrap();
+        assert_eq!(map.sources, vec!["orig.ts"]);
+        let foo = map
+            .mappings
+            .iter()
+            .find(|mapping| mapping.generated.column == column)
+            .unwrap();
+        assert_eq!(
+            foo.original,
+            Position {
+                line: 10,
+                column: 4
+            }
+        );
+        assert_eq!(map.names[foo.name.unwrap() as usize], "bar");
+    }
+}
@@ -1,11 +1,15 @@
 use std::fmt;
-#[derive(PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Token {
     STRING(StringObject),
     NUMBER(String),
+    /// A regex literal, flags included.
+    REGEX(String),
     Illegal,
     Operator(Operator),
-    Comma, // ,
+    Comma,     // ,
+    Semicolon, // ;
     LeftParen,
     RightParen,
     LeftBrace,
@@ -17,7 +21,81 @@ pub enum Token {
     Ident(String),
     EOF,
 }
-#[derive(PartialEq, Debug)]
+impl Token {
+    /// Whether this token can be the last token of an expression, which is
+    /// what decides if a line break after it may need a semicolon.
+    pub fn ends_expression(&self) -> bool {
+        match self {
+            Token::STRING(_) | Token::NUMBER(_) | Token::REGEX(_) | Token::Ident(_) => true,
+            Token::RightParen | Token::RightBrace | Token::RightBracket => true,
+            Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus) => true,
+            Token::Keyword(keyword) => matches!(
+                keyword,
+                Keyword::THIS | Keyword::TRUE | Keyword::FALSE | Keyword::NULL | Keyword::SUPER
+            ),
+            _ => false,
+        }
+    }
+
+    /// Precedence of the token when it is used between two operands. `?` and
+    /// `:` count as conditional operators and `=>` as an assignment.
+    pub fn binary_precedence(&self) -> Option<u8> {
+        match self {
+            Token::Comma => Some(precedence::COMMA),
+            Token::Keyword(Keyword::IN) | Token::Keyword(Keyword::INSTANCEOF) => {
+                Some(precedence::RELATIONAL)
+            }
+            Token::Operator(op) => op.binary_precedence(),
+            _ => None,
+        }
+    }
+}
+
+/// Binding strength of operators, from the comma operator up to member
+/// access and calls.
+pub mod precedence {
+    pub const COMMA: u8 = 1;
+    pub const ASSIGNMENT: u8 = 2;
+    pub const CONDITIONAL: u8 = 3;
+    pub const LOGICAL_OR: u8 = 4;
+    pub const LOGICAL_AND: u8 = 5;
+    pub const BITWISE_OR: u8 = 6;
+    pub const BITWISE_XOR: u8 = 7;
+    pub const BITWISE_AND: u8 = 8;
+    pub const EQUALITY: u8 = 9;
+    pub const RELATIONAL: u8 = 10;
+    pub const SHIFT: u8 = 11;
+    pub const ADDITIVE: u8 = 12;
+    pub const MULTIPLICATIVE: u8 = 13;
+    pub const EXPONENT: u8 = 14;
+    pub const UNARY: u8 = 15;
+    pub const POSTFIX: u8 = 16;
+    pub const MEMBER: u8 = 17;
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::STRING(string) => write!(f, "{}", string),
+            Token::NUMBER(number) => write!(f, "{}", number),
+            Token::REGEX(regex) => write!(f, "{}", regex),
+            Token::Operator(op) => write!(f, "{}", op),
+            Token::Keyword(keyword) => write!(f, "{}", keyword),
+            Token::Ident(value) => write!(f, "{}", value),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBrace => write!(f, "["),
+            Token::RightBrace => write!(f, "]"),
+            Token::LeftBracket => write!(f, "{{"),
+            Token::RightBracket => write!(f, "}}"),
+            Token::Illegal | Token::EOF => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct StringObject {
     pub value: String,
     pub t: StringType,
@@ -41,7 +119,7 @@ impl fmt::Display for StringObject {
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum StringType {
     SingleQuote,
     DoubleQuote,
@@ -59,7 +137,7 @@ impl fmt::Display for StringType {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operator {
     // Tradition Operators
     Plus,       // +
@@ -93,15 +171,22 @@ pub enum Operator {
     NullishCoalescing, // ??
 
     // Assignment Operators
-    Assign,           // =
-    PlusAssign,       // +=
-    MinusAssign,      // -=
-    AsteriskAssign,   // *=
-    SlashAssign,      // /=
-    ExponentAssign,   // **=
-    ModuloAssign,     // %=
-    BitwiseAndAssign, // &=
-    BitwiseOrAssign,  // |=
+    Assign,                     // =
+    PlusAssign,                 // +=
+    MinusAssign,                // -=
+    AsteriskAssign,             // *=
+    SlashAssign,                // /=
+    ExponentAssign,             // **=
+    ModuloAssign,               // %=
+    BitwiseAndAssign,           // &=
+    BitwiseOrAssign,            // |=
+    BitwiseXorAssign,           // ^=
+    BitwiseLeftAssign,          // <<=
+    BitwiseRightAssign,         // >>=
+    UnsignedBitwiseRightAssign, // >>>=
+    LogicalAndAssign,           // &&=
+    LogicalOrAssign,            // ||=
+    NullishAssign,              // ??=
 
     // Comparison Operators
     GreaterThan,      // >
@@ -114,7 +199,117 @@ pub enum Operator {
     StrictNotEqual,   // !==
 }
 
-#[derive(PartialEq, Debug)]
+impl Operator {
+    pub fn binary_precedence(&self) -> Option<u8> {
+        let prec = match self {
+            Operator::Arrow => precedence::ASSIGNMENT,
+            op if op.is_assignment() => precedence::ASSIGNMENT,
+            Operator::Ternary | Operator::Colon => precedence::CONDITIONAL,
+            Operator::LogicalOr | Operator::NullishCoalescing => precedence::LOGICAL_OR,
+            Operator::LogicalAnd => precedence::LOGICAL_AND,
+            Operator::BitwiseOr => precedence::BITWISE_OR,
+            Operator::BitwiseXor => precedence::BITWISE_XOR,
+            Operator::BitwiseAnd => precedence::BITWISE_AND,
+            Operator::Equal
+            | Operator::NotEqual
+            | Operator::StrictEqual
+            | Operator::StrictNotEqual => precedence::EQUALITY,
+            Operator::LessThan
+            | Operator::LessThanEqual
+            | Operator::GreaterThan
+            | Operator::GreaterThanEqual => precedence::RELATIONAL,
+            Operator::BitwiseLeft | Operator::BitwiseRight | Operator::UnsignedBitwiseRight => {
+                precedence::SHIFT
+            }
+            Operator::Plus | Operator::Minus => precedence::ADDITIVE,
+            Operator::Asterisk | Operator::Slash | Operator::Modulo => precedence::MULTIPLICATIVE,
+            Operator::Exponent => precedence::EXPONENT,
+            _ => return None,
+        };
+        Some(prec)
+    }
+
+    pub fn is_assignment(&self) -> bool {
+        matches!(
+            self,
+            Operator::Assign
+                | Operator::PlusAssign
+                | Operator::MinusAssign
+                | Operator::AsteriskAssign
+                | Operator::SlashAssign
+                | Operator::ExponentAssign
+                | Operator::ModuloAssign
+                | Operator::BitwiseAndAssign
+                | Operator::BitwiseOrAssign
+                | Operator::BitwiseXorAssign
+                | Operator::BitwiseLeftAssign
+                | Operator::BitwiseRightAssign
+                | Operator::UnsignedBitwiseRightAssign
+                | Operator::LogicalAndAssign
+                | Operator::LogicalOrAssign
+                | Operator::NullishAssign
+        )
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Operator::Arrow => "=>",
+            Operator::Assign => "=",
+            Operator::Asterisk => "*",
+            Operator::AsteriskAssign => "*=",
+            Operator::Bang => "!",
+            Operator::BitwiseAnd => "&",
+            Operator::BitwiseAndAssign => "&=",
+            Operator::BitwiseLeft => "<<",
+            Operator::BitwiseNot => "~",
+            Operator::BitwiseOr => "|",
+            Operator::BitwiseOrAssign => "|=",
+            Operator::BitwiseXorAssign => "^=",
+            Operator::BitwiseLeftAssign => "<<=",
+            Operator::BitwiseRightAssign => ">>=",
+            Operator::UnsignedBitwiseRightAssign => ">>>=",
+            Operator::LogicalAndAssign => "&&=",
+            Operator::LogicalOrAssign => "||=",
+            Operator::NullishAssign => "??=",
+            Operator::BitwiseRight => ">>",
+            Operator::BitwiseXor => "^",
+            Operator::Colon => ":",
+            Operator::Equal => "==",
+            Operator::Exponent => "**",
+            Operator::ExponentAssign => "**=",
+            Operator::GreaterThan => ">",
+            Operator::GreaterThanEqual => ">=",
+            Operator::LessThan => "<",
+            Operator::LessThanEqual => "<=",
+            Operator::LogicalAnd => "&&",
+            Operator::LogicalOr => "||",
+            Operator::Minus => "-",
+            Operator::MinusAssign => "-=",
+            Operator::MinusMinus => "--",
+            Operator::Modulo => "%",
+            Operator::ModuloAssign => "%=",
+            Operator::NotEqual => "!=",
+            Operator::NullishCoalescing => "??",
+            Operator::Period => ".",
+            Operator::Plus => "+",
+            Operator::PlusAssign => "+=",
+            Operator::PlusPlus => "++",
+            Operator::Slash => "/",
+            Operator::SlashAssign => "/=",
+            Operator::Spread => "...",
+            Operator::StrictEqual => "===",
+            Operator::StrictNotEqual => "!==",
+            Operator::Ternary => "?",
+            Operator::UnsignedBitwiseRight => ">>>",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Keyword {
     ASYNC,
     AWAIT,
@@ -165,6 +360,61 @@ pub enum Keyword {
     YIELD,
 }
 
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Keyword::ASYNC => "async",
+            Keyword::AWAIT => "await",
+            Keyword::BREAK => "break",
+            Keyword::CASE => "case",
+            Keyword::CATCH => "catch",
+            Keyword::CLASS => "class",
+            Keyword::CONST => "const",
+            Keyword::CONTINUE => "continue",
+            Keyword::DEBUGGER => "debugger",
+            Keyword::DEFAULT => "default",
+            Keyword::DELETE => "delete",
+            Keyword::DO => "do",
+            Keyword::ELSE => "else",
+            Keyword::ENUM => "enum",
+            Keyword::EXPORT => "export",
+            Keyword::EXTENDS => "extends",
+            Keyword::FALSE => "false",
+            Keyword::FINALLY => "finally",
+            Keyword::FOR => "for",
+            Keyword::FUNCTION => "function",
+            Keyword::IF => "if",
+            Keyword::IMPLEMENTS => "implements",
+            Keyword::IMPORT => "import",
+            Keyword::IN => "in",
+            Keyword::INSTANCEOF => "instanceof",
+            Keyword::INTERFACE => "interface",
+            Keyword::LET => "let",
+            Keyword::NEW => "new",
+            Keyword::NULL => "null",
+            Keyword::PACKAGE => "package",
+            Keyword::PRIVATE => "private",
+            Keyword::PROTECTED => "protected",
+            Keyword::PUBLIC => "public",
+            Keyword::RETURN => "return",
+            Keyword::SUPER => "super",
+            Keyword::SWITCH => "switch",
+            Keyword::STATIC => "static",
+            Keyword::THIS => "this",
+            Keyword::THROW => "throw",
+            Keyword::TRY => "try",
+            Keyword::TRUE => "true",
+            Keyword::TYPEOF => "typeof",
+            Keyword::VAR => "var",
+            Keyword::VOID => "void",
+            Keyword::WHILE => "while",
+            Keyword::WITH => "with",
+            Keyword::YIELD => "yield",
+        };
+        write!(f, "{}", value)
+    }
+}
+
 pub fn lookup_keyword(word: String) -> Token {
     match word.as_str() {
         "async" => Token::Keyword(Keyword::ASYNC),
@@ -218,7 +468,57 @@ pub fn lookup_keyword(word: String) -> Token {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum CommentType {
     SingleLine,
     MultiLine,
 }
+
+/// A comment, which is kept out of the token stream and attached to the
+/// token that follows it instead.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comment {
+    pub t: CommentType,
+    /// The text between the comment's delimiters.
+    pub text: String,
+}
+
+impl Comment {
+    /// The bundler annotation this comment holds, like `#__PURE__` in
+    /// `/* #__PURE__ */`.
+    pub fn annotation(&self) -> Option<&str> {
+        if self.t != CommentType::MultiLine {
+            return None;
+        }
+        match self.text.trim() {
+            text @ "#__PURE__"
+            | text @ "@__PURE__"
+            | text @ "#__NOINLINE__"
+            | text @ "@__NOINLINE__" => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a license header, like `/*! ... */` or one that says
+    /// `@license` or `@preserve`.
+    pub fn is_license(&self) -> bool {
+        (self.t == CommentType::MultiLine && self.text.starts_with('!'))
+            || self.text.contains("@license")
+            || self.text.contains("@preserve")
+    }
+
+    /// Whether writing this comment puts a line break in the output.
+    pub fn breaks_line(&self) -> bool {
+        self.t == CommentType::SingleLine
+            || self.text.contains(['\n', '\r', '\u{2028}', '\u{2029}'])
+    }
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.t {
+            CommentType::SingleLine => write!(f, "//{}", self.text),
+            CommentType::MultiLine => write!(f, "/*{}*/", self.text),
+        }
+    }
+}
@@ -0,0 +1,756 @@
+use std::ops::Range;
+
+use super::token::{precedence, Keyword, Operator, StringType, Token};
+
+fn is_open(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::LeftParen | Token::LeftBrace | Token::LeftBracket
+    )
+}
+
+fn is_close(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::RightParen | Token::RightBrace | Token::RightBracket
+    )
+}
+
+/// Finds the bracket that closes the one opened at `open`.
+pub fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, tok) in tokens.iter().enumerate().skip(open) {
+        if is_open(tok) {
+            depth += 1;
+        } else if is_close(tok) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Finds the bracket that opens the one closed at `close`.
+pub fn matching_open(tokens: &[Token], close: usize) -> Option<usize> {
+    let mut depth = 0;
+    for i in (0..=close).rev() {
+        if is_close(&tokens[i]) {
+            depth += 1;
+        } else if is_open(&tokens[i]) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Whether the `)` at `close` ends the head of an `if`, `for`, `while` or
+/// `with` statement, meaning a statement body follows it.
+pub fn closes_header(tokens: &[Token], close: usize) -> bool {
+    let open = match matching_open(tokens, close) {
+        Some(open) if open > 0 => open,
+        _ => return false,
+    };
+    match &tokens[open - 1] {
+        Token::Keyword(Keyword::IF)
+        | Token::Keyword(Keyword::FOR)
+        | Token::Keyword(Keyword::WHILE)
+        | Token::Keyword(Keyword::WITH) => true,
+        Token::Keyword(Keyword::AWAIT) => {
+            open > 1 && tokens[open - 2] == Token::Keyword(Keyword::FOR)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the statement at `i` is the whole body of an `if`, `else`, loop or
+/// `with`, so removing it has to leave an empty statement behind.
+pub fn is_body_position(tokens: &[Token], i: usize) -> bool {
+    if i == 0 {
+        return false;
+    }
+    match &tokens[i - 1] {
+        Token::RightParen => closes_header(tokens, i - 1),
+        Token::Keyword(Keyword::ELSE) | Token::Keyword(Keyword::DO) => true,
+        _ => false,
+    }
+}
+
+/// Whether the `:` at `colon` ends a `case` or `default` label.
+fn is_case_label(tokens: &[Token], colon: usize) -> bool {
+    let mut depth = 0;
+    let mut colons = 0;
+    for i in (0..colon).rev() {
+        match &tokens[i] {
+            tok if is_close(tok) => depth += 1,
+            tok if is_open(tok) => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            }
+            _ if depth > 0 => {}
+            Token::Keyword(Keyword::CASE) => return true,
+            Token::Keyword(Keyword::DEFAULT) => return i + 1 == colon,
+            Token::Operator(Operator::Colon) => colons += 1,
+            Token::Operator(Operator::Ternary) => {
+                if colons == 0 {
+                    return false;
+                }
+                colons -= 1;
+            }
+            Token::Semicolon => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whether a statement can begin at `i`.
+pub fn is_statement_start(tokens: &[Token], i: usize) -> bool {
+    if i == 0 {
+        return true;
+    }
+    match &tokens[i - 1] {
+        Token::Semicolon | Token::LeftBracket | Token::RightBracket => true,
+        Token::Operator(Operator::Colon) => is_case_label(tokens, i - 1),
+        _ => is_body_position(tokens, i),
+    }
+}
+
+/// Whether the `{` at `open` starts an object literal or pattern, rather than
+/// a block, function body or class body.
+pub fn is_object_literal(tokens: &[Token], open: usize) -> bool {
+    if open == 0 {
+        return false;
+    }
+    match &tokens[open - 1] {
+        Token::LeftParen | Token::LeftBrace | Token::Comma => true,
+        Token::Operator(Operator::Colon) => {
+            // the value of a property, or a branch of `?:`, but not the body
+            // of a label
+            match enclosing_open(tokens, open - 1) {
+                Some(outer)
+                    if tokens[outer] == Token::LeftBracket && is_object_literal(tokens, outer) =>
+                {
+                    true
+                }
+                _ => {
+                    let label = open > 1
+                        && matches!(tokens[open - 2], Token::Ident(_))
+                        && is_statement_start(tokens, open - 2);
+                    !label && !is_statement_start(tokens, open)
+                }
+            }
+        }
+        Token::Operator(operator) => !matches!(
+            operator,
+            Operator::Arrow | Operator::PlusPlus | Operator::MinusMinus
+        ),
+        Token::Keyword(keyword) => matches!(
+            keyword,
+            Keyword::RETURN
+                | Keyword::THROW
+                | Keyword::YIELD
+                | Keyword::AWAIT
+                | Keyword::TYPEOF
+                | Keyword::VOID
+                | Keyword::DELETE
+                | Keyword::IN
+                | Keyword::INSTANCEOF
+                | Keyword::CASE
+                | Keyword::DEFAULT
+                | Keyword::VAR
+                | Keyword::LET
+                | Keyword::CONST
+        ),
+        _ => false,
+    }
+}
+
+/// The bracket that `i` is directly inside of.
+pub fn enclosing_open(tokens: &[Token], i: usize) -> Option<usize> {
+    let mut depth = 0;
+    for k in (0..i).rev() {
+        match &tokens[k] {
+            tok if is_close(tok) => depth += 1,
+            tok if is_open(tok) => {
+                if depth == 0 {
+                    return Some(k);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether an expression has to be wrapped in parentheses to be used as a
+/// statement, because it would otherwise be read as a block, declaration or
+/// directive.
+pub fn needs_statement_parens(expr: &[Token]) -> bool {
+    match expr.first() {
+        Some(Token::STRING(string)) => expr.len() == 1 && string.t != StringType::Backtick,
+        Some(Token::LeftBracket)
+        | Some(Token::Keyword(Keyword::FUNCTION))
+        | Some(Token::Keyword(Keyword::CLASS)) => true,
+        Some(Token::Keyword(Keyword::ASYNC)) => {
+            expr.get(1) == Some(&Token::Keyword(Keyword::FUNCTION))
+        }
+        Some(Token::Keyword(Keyword::LET)) => expr.get(1) == Some(&Token::LeftBrace),
+        _ => false,
+    }
+}
+
+/// Splits `tokens` on the commas that are not nested in brackets.
+pub fn split_top_level(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, tok) in tokens.iter().enumerate() {
+        if is_open(tok) {
+            depth += 1;
+        } else if is_close(tok) {
+            depth -= 1;
+        } else if *tok == Token::Comma && depth == 0 {
+            parts.push(start..i);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        parts.push(start..tokens.len());
+    }
+    parts
+}
+
+/// Whether evaluating the expression in `tokens` can't have side effects.
+/// Only literals and plain names count, since reading a property can run a
+/// getter and most operators can call `valueOf` or `toString`.
+pub fn is_side_effect_free(tokens: &[Token]) -> bool {
+    match tokens {
+        [Token::Operator(Operator::Minus), Token::NUMBER(_)] => true,
+        [Token::STRING(string)] => string.t != StringType::Backtick || !string.value.contains("${"),
+        [Token::NUMBER(_)] | [Token::REGEX(_)] => true,
+        [Token::Ident(name)] => !name.starts_with('#'),
+        [Token::Keyword(keyword)] => matches!(
+            keyword,
+            Keyword::THIS | Keyword::TRUE | Keyword::FALSE | Keyword::NULL
+        ),
+        _ => false,
+    }
+}
+
+/// Whether `tok` can be part of an expression without being an operator
+/// between two operands, like `typeof`, `this` or `function`.
+fn is_operand_keyword(keyword: &Keyword) -> bool {
+    matches!(
+        keyword,
+        Keyword::THIS
+            | Keyword::SUPER
+            | Keyword::TRUE
+            | Keyword::FALSE
+            | Keyword::NULL
+            | Keyword::TYPEOF
+            | Keyword::VOID
+            | Keyword::DELETE
+            | Keyword::AWAIT
+            | Keyword::NEW
+            | Keyword::FUNCTION
+            | Keyword::CLASS
+            | Keyword::ASYNC
+            | Keyword::EXTENDS
+    )
+}
+
+/// Whether the token at `i` is used as a binary operator, rather than as a
+/// unary `+`/`-` in front of an operand.
+pub fn is_binary_at(tokens: &[Token], i: usize) -> bool {
+    if tokens[i].binary_precedence().is_none() {
+        return false;
+    }
+    match &tokens[i] {
+        Token::Operator(Operator::Plus) | Token::Operator(Operator::Minus) => {
+            i > 0
+                && tokens[i - 1].ends_expression()
+                && !(tokens[i - 1] == Token::RightParen && closes_header(tokens, i - 1))
+        }
+        _ => true,
+    }
+}
+
+/// Finds where the expression ending at `end` starts, taking in operators
+/// that bind at least as tightly as `min_prec`.
+pub fn operand_start(tokens: &[Token], end: usize, min_prec: u8) -> usize {
+    let mut i = end;
+    while i > 0 {
+        let k = i - 1;
+        let tok = &tokens[k];
+        if is_close(tok) {
+            if *tok == Token::RightParen && closes_header(tokens, k) {
+                break;
+            }
+            // a block ending right before the operand
+            if *tok == Token::RightBracket && i < end && !continues_expression(&tokens[i]) {
+                break;
+            }
+            match matching_open(tokens, k) {
+                Some(open) => i = open,
+                None => break,
+            }
+            continue;
+        }
+        if is_open(tok) || *tok == Token::Semicolon {
+            break;
+        }
+        if is_binary_at(tokens, k) {
+            if tok.binary_precedence().unwrap_or(0) < min_prec {
+                break;
+            }
+        } else if let Token::Keyword(keyword) = tok {
+            if !is_operand_keyword(keyword) {
+                break;
+            }
+        } else if let Token::Operator(Operator::Colon) = tok {
+            break;
+        }
+        i = k;
+    }
+    i
+}
+
+/// Whether `tok` continues an expression that came before it, like an
+/// operator or a call, rather than starting a new one.
+fn continues_expression(tok: &Token) -> bool {
+    match tok {
+        Token::Operator(op) => !matches!(
+            op,
+            Operator::Bang | Operator::BitwiseNot | Operator::PlusPlus | Operator::MinusMinus
+        ),
+        Token::Keyword(Keyword::IN) | Token::Keyword(Keyword::INSTANCEOF) => true,
+        Token::LeftParen | Token::LeftBrace | Token::Comma => true,
+        Token::STRING(string) => string.t == StringType::Backtick,
+        _ => false,
+    }
+}
+
+/// Finds where the expression starting at `start` ends, taking in operators
+/// that bind at least as tightly as `min_prec`.
+pub fn operand_end(tokens: &[Token], start: usize, min_prec: u8) -> usize {
+    let mut i = start;
+    // whether the tokens so far form a complete operand
+    let mut operand = false;
+    while i < tokens.len() {
+        let tok = &tokens[i];
+        if is_open(tok) {
+            match matching_close(tokens, i) {
+                Some(close) => i = close + 1,
+                None => return tokens.len(),
+            }
+            operand = true;
+            continue;
+        }
+        if is_close(tok) || *tok == Token::Semicolon {
+            break;
+        }
+        if operand {
+            if let Some(prec) = tok.binary_precedence() {
+                if prec < min_prec {
+                    break;
+                }
+                operand = false;
+            } else {
+                match tok {
+                    Token::Operator(Operator::Period) => operand = false,
+                    Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus) => {
+                    }
+                    Token::STRING(string) if string.t == StringType::Backtick => {}
+                    _ => break,
+                }
+            }
+        } else {
+            match tok {
+                Token::Keyword(keyword) if !is_operand_keyword(keyword) => break,
+                Token::Keyword(Keyword::FUNCTION)
+                | Token::Keyword(Keyword::CLASS)
+                | Token::Keyword(Keyword::EXTENDS) => operand = false,
+                tok => operand = tok.ends_expression(),
+            }
+        }
+        i += 1;
+    }
+    i
+}
+
+/// The lowest precedence of the operators in `expr`, outside of any
+/// brackets.
+pub fn expression_precedence(expr: &[Token]) -> u8 {
+    let mut prec = precedence::MEMBER;
+    let mut i = 0;
+    while i < expr.len() {
+        let tok = &expr[i];
+        if is_open(tok) {
+            i = matching_close(expr, i).unwrap_or(expr.len()) + 1;
+            continue;
+        }
+        if *tok == Token::Keyword(Keyword::YIELD) {
+            prec = prec.min(precedence::ASSIGNMENT);
+        } else if is_binary_at(expr, i) {
+            prec = prec.min(tok.binary_precedence().unwrap_or(prec));
+        }
+        i += 1;
+    }
+    match expr.first() {
+        Some(Token::Operator(Operator::Bang))
+        | Some(Token::Operator(Operator::BitwiseNot))
+        | Some(Token::Operator(Operator::Plus))
+        | Some(Token::Operator(Operator::Minus))
+        | Some(Token::Operator(Operator::PlusPlus))
+        | Some(Token::Operator(Operator::MinusMinus))
+        | Some(Token::Keyword(Keyword::TYPEOF))
+        | Some(Token::Keyword(Keyword::VOID))
+        | Some(Token::Keyword(Keyword::DELETE))
+        | Some(Token::Keyword(Keyword::AWAIT)) => prec = prec.min(precedence::UNARY),
+        // `new a` without arguments can't be called or have members read
+        Some(Token::Keyword(Keyword::NEW)) => prec = prec.min(precedence::POSTFIX),
+        _ => {}
+    }
+    if expr.len() > 1
+        && matches!(
+            expr[expr.len() - 1],
+            Token::Operator(Operator::PlusPlus) | Token::Operator(Operator::MinusMinus)
+        )
+    {
+        prec = prec.min(precedence::POSTFIX);
+    }
+    prec
+}
+
+/// Whether a token in `tokens` that is outside of any brackets matches `f`.
+pub fn has_top_level<F: Fn(&Token) -> bool>(tokens: &[Token], f: F) -> bool {
+    let mut i = 0;
+    while i < tokens.len() {
+        let tok = &tokens[i];
+        if is_open(tok) {
+            i = matching_close(tokens, i).unwrap_or(tokens.len()) + 1;
+            continue;
+        }
+        if f(tok) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Finds the end of the statement starting at `start`, including the
+/// semicolon that terminates it.
+pub fn statement_end(tokens: &[Token], start: usize) -> usize {
+    let after_group = |open: usize| match matching_close(tokens, open) {
+        Some(close) => close + 1,
+        None => tokens.len(),
+    };
+    let find = |from: usize, target: &Token| {
+        let mut i = from;
+        while i < tokens.len() && tokens[i] != *target {
+            i = if is_open(&tokens[i]) {
+                after_group(i)
+            } else {
+                i + 1
+            };
+        }
+        i
+    };
+    match tokens.get(start) {
+        None => start,
+        Some(Token::Semicolon) => start + 1,
+        Some(Token::LeftBracket) => after_group(start),
+        Some(Token::Keyword(Keyword::IF)) => {
+            let end = statement_end(tokens, after_group(start + 1));
+            match tokens.get(end) {
+                Some(Token::Keyword(Keyword::ELSE)) => statement_end(tokens, end + 1),
+                _ => end,
+            }
+        }
+        Some(Token::Keyword(Keyword::FOR))
+        | Some(Token::Keyword(Keyword::WHILE))
+        | Some(Token::Keyword(Keyword::WITH)) => {
+            let open = find(start + 1, &Token::LeftParen);
+            statement_end(tokens, after_group(open))
+        }
+        Some(Token::Keyword(Keyword::DO)) => {
+            let end = statement_end(tokens, start + 1);
+            let end = after_group(end + 1);
+            match tokens.get(end) {
+                Some(Token::Semicolon) => end + 1,
+                _ => end,
+            }
+        }
+        Some(Token::Keyword(Keyword::SWITCH)) => after_group(after_group(start + 1)),
+        Some(Token::Keyword(Keyword::TRY)) => {
+            let mut end = after_group(start + 1);
+            if let Some(Token::Keyword(Keyword::CATCH)) = tokens.get(end) {
+                end = after_group(find(end, &Token::LeftBracket));
+            }
+            if let Some(Token::Keyword(Keyword::FINALLY)) = tokens.get(end) {
+                end = after_group(end + 1);
+            }
+            end
+        }
+        Some(Token::Keyword(Keyword::FUNCTION)) | Some(Token::Keyword(Keyword::CLASS)) => {
+            after_group(find(start, &Token::LeftBracket))
+        }
+        Some(Token::Keyword(Keyword::ASYNC))
+            if tokens.get(start + 1) == Some(&Token::Keyword(Keyword::FUNCTION)) =>
+        {
+            after_group(find(start, &Token::LeftBracket))
+        }
+        Some(Token::Keyword(Keyword::EXPORT)) => {
+            let mut next = start + 1;
+            if let Some(Token::Keyword(Keyword::DEFAULT)) = tokens.get(next) {
+                next += 1;
+            }
+            match tokens.get(next) {
+                Some(Token::Keyword(Keyword::FUNCTION))
+                | Some(Token::Keyword(Keyword::CLASS))
+                | Some(Token::Keyword(Keyword::ASYNC)) => statement_end(tokens, next),
+                _ => simple_statement_end(tokens, start),
+            }
+        }
+        Some(Token::Ident(_))
+            if tokens.get(start + 1) == Some(&Token::Operator(Operator::Colon)) =>
+        {
+            statement_end(tokens, start + 2)
+        }
+        Some(_) => simple_statement_end(tokens, start),
+    }
+}
+
+/// End of a statement without a body, which runs up to its semicolon or the
+/// end of the enclosing block.
+fn simple_statement_end(tokens: &[Token], start: usize) -> usize {
+    let mut i = start;
+    while i < tokens.len() {
+        let tok = &tokens[i];
+        if is_open(tok) {
+            match matching_close(tokens, i) {
+                Some(close) => i = close + 1,
+                None => return tokens.len(),
+            }
+            continue;
+        }
+        if is_close(tok) {
+            return i;
+        }
+        if *tok == Token::Semicolon {
+            return i + 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Whether the block or statement list in `tokens` declares block scoped
+/// bindings at its top level, which would leak out if its braces were
+/// removed.
+pub fn has_lexical_declaration(tokens: &[Token]) -> bool {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Keyword(Keyword::LET)
+            | Token::Keyword(Keyword::CONST)
+            | Token::Keyword(Keyword::CLASS)
+                if is_statement_start(tokens, i) =>
+            {
+                return true
+            }
+            Token::Keyword(Keyword::FUNCTION) if is_function_declaration(tokens, i) => return true,
+            tok if is_open(tok) => match matching_close(tokens, i) {
+                Some(close) => i = close,
+                None => return false,
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Whether the `{` at `open` starts the body of a function, method or arrow
+/// function.
+pub fn is_function_body(tokens: &[Token], open: usize) -> bool {
+    if open == 0 {
+        return false;
+    }
+    match &tokens[open - 1] {
+        Token::Operator(Operator::Arrow) => true,
+        Token::RightParen => match matching_open(tokens, open - 1) {
+            Some(paren) if paren > 0 => !matches!(
+                tokens[paren - 1],
+                Token::Keyword(Keyword::IF)
+                    | Token::Keyword(Keyword::FOR)
+                    | Token::Keyword(Keyword::WHILE)
+                    | Token::Keyword(Keyword::WITH)
+                    | Token::Keyword(Keyword::SWITCH)
+                    | Token::Keyword(Keyword::CATCH)
+                    | Token::Keyword(Keyword::AWAIT)
+            ),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Whether the `function` keyword at `i` starts a function declaration,
+/// which can follow `async` or labels like in `a:function f(){}`.
+pub fn is_function_declaration(tokens: &[Token], mut i: usize) -> bool {
+    if i > 0 && tokens[i - 1] == Token::Keyword(Keyword::ASYNC) {
+        i -= 1;
+    }
+    // not the key of an object literal, like in `{a:function(){}}`
+    while i > 1
+        && tokens[i - 1] == Token::Operator(Operator::Colon)
+        && matches!(tokens[i - 2], Token::Ident(_))
+        && !is_case_label(tokens, i - 1)
+        && !(i > 2 && tokens[i - 3] == Token::LeftBracket && is_object_literal(tokens, i - 3))
+    {
+        i -= 2;
+    }
+    is_statement_start(tokens, i)
+}
+
+/// Collects the names that `var` and function declarations in `tokens` add
+/// to the enclosing function scope, skipping nested functions. Function
+/// declarations inside blocks only count as `var` bindings, as they do in
+/// sloppy mode.
+pub fn var_declarations(tokens: &[Token]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Keyword(Keyword::FUNCTION) if is_function_declaration(tokens, i) => {
+                let name = tokens[i + 1..]
+                    .iter()
+                    .find(|tok| **tok != Token::Operator(Operator::Asterisk));
+                if let Some(Token::Ident(name)) = name {
+                    names.push(name.clone());
+                }
+            }
+            Token::LeftBracket if is_function_body(tokens, i) => match matching_close(tokens, i) {
+                Some(close) => i = close,
+                None => break,
+            },
+            Token::Keyword(Keyword::VAR) => {
+                let end = declaration_end(tokens, i + 1);
+                names.extend(declared_names(&tokens[i + 1..end]));
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+/// Finds the end of the declarator list of a `var`, `let` or `const` that
+/// starts at `start`, which is where its semicolon is or where a `for`
+/// loop's `in`/`of` begins.
+pub fn declaration_end(tokens: &[Token], start: usize) -> usize {
+    let mut i = start;
+    while i < tokens.len() {
+        match &tokens[i] {
+            tok if is_open(tok) => match matching_close(tokens, i) {
+                Some(close) => i = close,
+                None => return tokens.len(),
+            },
+            tok if is_close(tok) => return i,
+            Token::Semicolon | Token::Keyword(Keyword::IN) => return i,
+            Token::Ident(name) if name == "of" && i > start && tokens[i - 1].ends_expression() => {
+                return i
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Names bound by a declarator list like `a = 1, {b, c: d} = e, [f]`.
+pub fn declared_names(tokens: &[Token]) -> Vec<String> {
+    let mut names = Vec::new();
+    for range in split_top_level(tokens) {
+        let declarator = &tokens[range];
+        let mut depth = 0;
+        let assign = declarator.iter().position(|tok| {
+            if is_open(tok) {
+                depth += 1;
+            } else if is_close(tok) {
+                depth -= 1;
+            }
+            depth == 0 && *tok == Token::Operator(Operator::Assign)
+        });
+        let target = match assign {
+            Some(assign) => &declarator[..assign],
+            None => declarator,
+        };
+        collect_pattern_names(target, &mut names);
+    }
+    names
+}
+
+/// Collects the bindings in a destructuring pattern, skipping property keys
+/// and default values.
+fn collect_pattern_names(pattern: &[Token], names: &mut Vec<String>) {
+    let mut i = 0;
+    while i < pattern.len() {
+        match &pattern[i] {
+            Token::Ident(name) => {
+                let next = pattern.get(i + 1);
+                if next != Some(&Token::Operator(Operator::Colon)) {
+                    names.push(name.clone());
+                }
+            }
+            Token::Operator(Operator::Assign) => {
+                // skip the default value
+                let mut depth = 0;
+                i += 1;
+                while i < pattern.len() {
+                    match &pattern[i] {
+                        tok if is_open(tok) => depth += 1,
+                        tok if is_close(tok) => {
+                            if depth == 0 {
+                                break;
+                            }
+                            depth -= 1;
+                        }
+                        Token::Comma if depth == 0 => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+            Token::LeftBrace => {
+                // `[key]: value` is a computed key rather than a nested pattern
+                if let Some(close) = matching_close(pattern, i) {
+                    if pattern.get(close + 1) == Some(&Token::Operator(Operator::Colon)) {
+                        i = close;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}